@@ -3,5 +3,20 @@
 #[cfg(feature = "async-read-body")]
 mod async_read_body;
 
+#[cfg(feature = "attachment")]
+mod sized_stream;
+
+#[cfg(feature = "attachment-tee")]
+mod tee_body;
+
 #[cfg(feature = "async-read-body")]
 pub use self::async_read_body::AsyncReadBody;
+
+#[cfg(feature = "attachment")]
+pub use self::sized_stream::{DownloadAborted, SizedStream};
+
+#[cfg(feature = "attachment-digest")]
+pub use self::sized_stream::DigestMismatch;
+
+#[cfg(feature = "attachment-tee")]
+pub use self::tee_body::{SlowSink, TeeBody};
@@ -0,0 +1,447 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    response::{IntoResponse, Response},
+    BoxError, Error,
+};
+use futures_util::{ready, stream::TryStream};
+use http_body::{Frame, SizeHint};
+use pin_project_lite::pin_project;
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+pin_project! {
+    /// An [`HttpBody`] created from a [`TryStream`] with a known length.
+    ///
+    /// Declaring the length up front allows hyper to set the `Content-Length` header, which
+    /// streamed bodies normally lack. When used with [`Attachment`], the declared length is also
+    /// reported through [`AttachmentInfo::size`].
+    ///
+    /// In debug builds this panics if the stream produces a different number of bytes than
+    /// declared. In release builds a stream that ends early, produces too many bytes, or fails,
+    /// produces a [`DownloadAborted`] error instead of silently truncating the body. hyper reacts to body
+    /// errors by resetting the HTTP/2 stream or closing the HTTP/1 connection, so clients can tell
+    /// the download is incomplete.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::{response::IntoResponse, routing::get, Router};
+    /// use axum_extra::{body::SizedStream, response::Attachment};
+    /// use futures_util::stream;
+    /// use std::convert::Infallible;
+    ///
+    /// async fn report() -> impl IntoResponse {
+    ///     let chunks = stream::iter(["id,name\n", "1,ferris\n"].map(Ok::<_, Infallible>));
+    ///     Attachment::new(SizedStream::new(chunks, 17)).filename("report.csv")
+    /// }
+    ///
+    /// let app = Router::new().route("/report.csv", get(report));
+    /// # let _: Router = app;
+    /// ```
+    ///
+    /// [`Attachment`]: crate::response::Attachment
+    /// [`AttachmentInfo::size`]: crate::response::AttachmentInfo::size
+    #[derive(Debug)]
+    #[must_use]
+    pub struct SizedStream<S> {
+        #[pin]
+        stream: S,
+        len: u64,
+        sent: u64,
+        verifier: Option<Verifier>,
+    }
+}
+
+impl<S> SizedStream<S> {
+    /// Create a new `SizedStream` from a stream that produces exactly `len` bytes.
+    pub fn new(stream: S, len: u64) -> Self {
+        Self {
+            stream,
+            len,
+            sent: 0,
+            verifier: None,
+        }
+    }
+
+    /// Verify that the stream matches the SHA-256 digest `expected`.
+    ///
+    /// The stream is hashed while it is sent. If the digest doesn't match once the declared length
+    /// has been produced, the last chunk is held back and the body fails with a
+    /// [`DigestMismatch`] error instead, and the mismatch is logged, so clients can tell the
+    /// download is corrupt.
+    #[cfg(feature = "attachment-digest")]
+    pub fn verify_digest(mut self, expected: [u8; 32]) -> Self {
+        self.verifier = Some(Verifier::new(expected));
+        self
+    }
+}
+
+impl<S> HttpBody for SizedStream<S>
+where
+    S: TryStream,
+    S::Ok: Into<Bytes>,
+    S::Error: Into<BoxError>,
+{
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+
+        match ready!(this.stream.try_poll_next(cx)) {
+            Some(Ok(chunk)) => {
+                let chunk = chunk.into();
+                let sent_before = *this.sent;
+                *this.sent += chunk.len() as u64;
+                debug_assert!(
+                    *this.sent <= *this.len,
+                    "`SizedStream` produced more bytes than the declared length of {}",
+                    this.len,
+                );
+
+                if *this.sent > *this.len {
+                    return Poll::Ready(Some(Err(Error::new(DownloadAborted {
+                        sent: sent_before,
+                        total: *this.len,
+                        source: None,
+                    }))));
+                }
+
+                if let Some(verifier) = this.verifier.as_mut() {
+                    verifier.update(&chunk);
+                }
+                // hyper stops polling once `Content-Length` bytes have been sent, so the digest
+                // has to be checked before the last chunk goes out
+                if *this.sent == *this.len {
+                    if let Some(verifier) = this.verifier.take() {
+                        if let Err(err) = verifier.finish() {
+                            tracing::error!(%err, "`SizedStream` doesn't match its expected digest");
+                            return Poll::Ready(Some(Err(Error::new(err))));
+                        }
+                    }
+                }
+
+                Poll::Ready(Some(Ok(Frame::data(chunk))))
+            }
+            Some(Err(err)) => Poll::Ready(Some(Err(Error::new(DownloadAborted {
+                sent: *this.sent,
+                total: *this.len,
+                source: Some(err.into()),
+            })))),
+            None => {
+                debug_assert!(
+                    *this.sent == *this.len,
+                    "`SizedStream` ended after {} bytes but declared a length of {}",
+                    this.sent,
+                    this.len,
+                );
+
+                if *this.sent < *this.len {
+                    return Poll::Ready(Some(Err(Error::new(DownloadAborted {
+                        sent: *this.sent,
+                        total: *this.len,
+                        source: None,
+                    }))));
+                }
+
+                if let Some(verifier) = this.verifier.take() {
+                    if let Err(err) = verifier.finish() {
+                        tracing::error!(%err, "`SizedStream` doesn't match its expected digest");
+                        return Poll::Ready(Some(Err(Error::new(err))));
+                    }
+                }
+
+                Poll::Ready(None)
+            }
+        }
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.len.saturating_sub(self.sent))
+    }
+}
+
+impl<S> IntoResponse for SizedStream<S>
+where
+    S: TryStream + Send + 'static,
+    S::Ok: Into<Bytes>,
+    S::Error: Into<BoxError>,
+{
+    fn into_response(self) -> Response {
+        Body::new(self).into_response()
+    }
+}
+
+/// Error produced by [`SizedStream`] when the download is aborted before all bytes were sent.
+///
+/// This is wrapped in an [`axum::Error`] and can be recovered with
+/// [`into_inner`](axum::Error::into_inner) and [`downcast`](std::error::Error#method.downcast).
+#[derive(Debug)]
+pub struct DownloadAborted {
+    sent: u64,
+    total: u64,
+    source: Option<BoxError>,
+}
+
+impl DownloadAborted {
+    /// The number of bytes that were sent before the download was aborted.
+    pub fn sent(&self) -> u64 {
+        self.sent
+    }
+
+    /// The declared length of the download.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+}
+
+impl fmt::Display for DownloadAborted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "download aborted after {} of {} bytes",
+            self.sent, self.total
+        )
+    }
+}
+
+impl std::error::Error for DownloadAborted {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|err| err as _)
+    }
+}
+
+#[cfg(feature = "attachment-digest")]
+struct Verifier {
+    hasher: sha2::Sha256,
+    expected: [u8; 32],
+}
+
+#[cfg(feature = "attachment-digest")]
+impl Verifier {
+    fn new(expected: [u8; 32]) -> Self {
+        Self {
+            hasher: sha2::Digest::new(),
+            expected,
+        }
+    }
+
+    fn update(&mut self, chunk: &[u8]) {
+        sha2::Digest::update(&mut self.hasher, chunk);
+    }
+
+    fn finish(self) -> Result<(), DigestMismatch> {
+        let actual: [u8; 32] = sha2::Digest::finalize(self.hasher).into();
+        if actual == self.expected {
+            Ok(())
+        } else {
+            Err(DigestMismatch {
+                expected: self.expected,
+                actual,
+            })
+        }
+    }
+}
+
+#[cfg(not(feature = "attachment-digest"))]
+enum Verifier {}
+
+#[cfg(not(feature = "attachment-digest"))]
+impl Verifier {
+    fn update(&mut self, _chunk: &[u8]) {
+        match *self {}
+    }
+
+    fn finish(self) -> Result<(), std::convert::Infallible> {
+        match self {}
+    }
+}
+
+impl fmt::Debug for Verifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Verifier").finish_non_exhaustive()
+    }
+}
+
+/// Error produced by [`SizedStream`] when the stream doesn't match the digest passed to
+/// [`verify_digest`](SizedStream::verify_digest).
+///
+/// This is wrapped in an [`axum::Error`] and can be recovered with
+/// [`into_inner`](axum::Error::into_inner) and [`downcast`](std::error::Error#method.downcast).
+#[cfg(feature = "attachment-digest")]
+#[derive(Debug)]
+pub struct DigestMismatch {
+    expected: [u8; 32],
+    actual: [u8; 32],
+}
+
+#[cfg(feature = "attachment-digest")]
+impl DigestMismatch {
+    /// The expected SHA-256 digest.
+    pub fn expected(&self) -> [u8; 32] {
+        self.expected
+    }
+
+    /// The SHA-256 digest of the data that was sent.
+    pub fn actual(&self) -> [u8; 32] {
+        self.actual
+    }
+}
+
+#[cfg(feature = "attachment-digest")]
+impl fmt::Display for DigestMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("SHA-256 digest mismatch: expected ")?;
+        for byte in self.expected {
+            write!(f, "{byte:02x}")?;
+        }
+        f.write_str(", got ")?;
+        for byte in self.actual {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "attachment-digest")]
+impl std::error::Error for DigestMismatch {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::{Attachment, AttachmentInfo};
+    use futures_util::stream;
+    use http_body_util::BodyExt;
+    use std::convert::Infallible;
+
+    fn chunks(
+        chunks: &'static [&'static str],
+    ) -> impl TryStream<Ok = &'static str, Error = Infallible> {
+        stream::iter(chunks.iter().copied().map(Ok))
+    }
+
+    #[tokio::test]
+    async fn reports_declared_length() {
+        let response =
+            Attachment::new(SizedStream::new(chunks(&["foo", "bar"]), 6)).into_response();
+
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.size(), Some(6));
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "foobar");
+    }
+
+    #[tokio::test]
+    async fn source_error_aborts_download() {
+        let chunks = stream::iter(vec![
+            Ok("foo"),
+            Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                "disk on fire",
+            )),
+        ]);
+        let mut body = SizedStream::new(chunks, 6);
+
+        let frame = body.frame().await.unwrap().unwrap();
+        assert_eq!(frame.into_data().unwrap(), "foo");
+
+        let err = body.frame().await.unwrap().unwrap_err();
+        let err = err.into_inner().downcast::<DownloadAborted>().unwrap();
+        assert_eq!(err.sent(), 3);
+        assert_eq!(err.total(), 6);
+        assert_eq!(
+            std::error::Error::source(&*err).unwrap().to_string(),
+            "disk on fire"
+        );
+    }
+
+    #[cfg(feature = "attachment-digest")]
+    #[tokio::test]
+    async fn verifies_digest() {
+        use sha2::{Digest, Sha256};
+
+        let expected: [u8; 32] = Sha256::digest(b"foobar").into();
+        let body = SizedStream::new(chunks(&["foo", "bar"]), 6).verify_digest(expected);
+        let bytes = body.collect().await.unwrap().to_bytes();
+        assert_eq!(bytes, "foobar");
+
+        let body = SizedStream::new(chunks(&["foo", "baz"]), 6).verify_digest(expected);
+        let err = body.collect().await.unwrap_err();
+        let err = err.into_inner().downcast::<DigestMismatch>().unwrap();
+        assert_eq!(err.expected(), expected);
+        assert_eq!(err.actual(), <[u8; 32]>::from(Sha256::digest(b"foobaz")));
+    }
+
+    #[cfg(feature = "attachment-digest")]
+    #[crate::test]
+    async fn digest_mismatch_aborts_http_download() {
+        use crate::test_helpers::spawn_service;
+        use axum::{routing::get, Router};
+        use sha2::{Digest, Sha256};
+
+        let expected: [u8; 32] = Sha256::digest(b"foobar").into();
+        let app = Router::new()
+            .route(
+                "/ok",
+                get(move || async move {
+                    SizedStream::new(chunks(&["foo", "bar"]), 6).verify_digest(expected)
+                }),
+            )
+            .route(
+                "/corrupt",
+                get(move || async move {
+                    SizedStream::new(chunks(&["foo", "baz"]), 6).verify_digest(expected)
+                }),
+            );
+        let addr = spawn_service(app);
+
+        let res = reqwest::get(format!("http://{addr}/ok")).await.unwrap();
+        assert_eq!(res.bytes().await.unwrap(), "foobar");
+
+        // depending on when the error happens, hyper fails the request or the body
+        let corrupt = async {
+            reqwest::get(format!("http://{addr}/corrupt"))
+                .await?
+                .bytes()
+                .await
+        };
+        assert!(corrupt.await.is_err());
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[tokio::test]
+    async fn long_stream_aborts_in_release() {
+        let mut body = SizedStream::new(chunks(&["foo", "bar"]), 4);
+
+        let frame = body.frame().await.unwrap().unwrap();
+        assert_eq!(frame.into_data().unwrap(), "foo");
+
+        let err = body.frame().await.unwrap().unwrap_err();
+        let err = err.into_inner().downcast::<DownloadAborted>().unwrap();
+        assert_eq!(err.sent(), 3);
+        assert_eq!(err.total(), 4);
+    }
+
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    #[should_panic(expected = "ended after 3 bytes but declared a length of 6")]
+    async fn panics_on_short_stream_in_debug() {
+        let body = SizedStream::new(chunks(&["foo"]), 6);
+        let _ = body.collect().await;
+    }
+
+    #[cfg(debug_assertions)]
+    #[tokio::test]
+    #[should_panic(expected = "produced more bytes than the declared length of 2")]
+    async fn panics_on_long_stream_in_debug() {
+        let body = SizedStream::new(chunks(&["foo"]), 2);
+        let _ = body.collect().await;
+    }
+}
@@ -0,0 +1,263 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    response::{IntoResponse, Response},
+    BoxError, Error,
+};
+use bytes::{Buf, BytesMut};
+use futures_util::ready;
+use http_body::{Frame, SizeHint};
+use pin_project_lite::pin_project;
+use std::{
+    io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::AsyncWrite;
+
+/// What a [`TeeBody`] does when its sink can't keep up with the client.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SlowSink {
+    /// Wait for the sink, slowing the download down to the speed of the sink.
+    ///
+    /// If writing to the sink fails, the download is aborted as well.
+    Wait,
+    /// Buffer up to `max_buffered` bytes for the sink, and stop copying if the sink falls further
+    /// behind or fails.
+    ///
+    /// The download continues either way, so the copy may be incomplete. Once the body has ended,
+    /// the remaining buffered bytes are written and the sink is shut down as far as the sink allows
+    /// without waiting, after which it is dropped so the download can finish.
+    Detach {
+        /// The maximum number of bytes waiting to be written to the sink.
+        max_buffered: usize,
+    },
+}
+
+pin_project! {
+    /// An [`HttpBody`] that copies everything it sends into an [`AsyncWrite`].
+    ///
+    /// This allows archiving a download, for example to a local file or an upload to object
+    /// storage, while it is streamed to the client. The sink is shut down once the body ends.
+    ///
+    /// By default the download waits for the sink, see [`SlowSink`] for alternatives.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::{body::Body, response::IntoResponse, routing::get, Router};
+    /// use axum_extra::{body::TeeBody, response::Attachment};
+    ///
+    /// async fn report() -> impl IntoResponse {
+    ///     let archive = tokio::fs::File::create("archive/report.csv").await.unwrap();
+    ///     let body = TeeBody::new(Body::from("id,name\n1,ferris\n"), archive);
+    ///     Attachment::new(body).filename("report.csv")
+    /// }
+    ///
+    /// let app = Router::new().route("/report.csv", get(report));
+    /// # let _: Router = app;
+    /// ```
+    #[derive(Debug)]
+    #[must_use]
+    pub struct TeeBody<B, W> {
+        #[pin]
+        body: B,
+        sink: Option<W>,
+        buffer: BytesMut,
+        policy: SlowSink,
+        body_done: bool,
+    }
+}
+
+impl<B, W> TeeBody<B, W> {
+    /// Create a new `TeeBody` that copies `body` into `sink`.
+    pub fn new(body: B, sink: W) -> Self {
+        Self {
+            body,
+            sink: Some(sink),
+            buffer: BytesMut::new(),
+            policy: SlowSink::Wait,
+            body_done: false,
+        }
+    }
+
+    /// Set what to do when the sink can't keep up with the client.
+    pub fn slow_sink(mut self, policy: SlowSink) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+impl<B, W> HttpBody for TeeBody<B, W>
+where
+    B: HttpBody<Data = Bytes>,
+    B::Error: Into<BoxError>,
+    W: AsyncWrite + Unpin,
+{
+    type Data = Bytes;
+    type Error = Error;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+
+        loop {
+            if let Some(sink) = this.sink.as_mut() {
+                let result = match poll_write_buffer(sink, this.buffer, cx) {
+                    Poll::Ready(Ok(())) if *this.body_done => Pin::new(sink).poll_shutdown(cx),
+                    poll => poll,
+                };
+                let result = match result {
+                    Poll::Ready(result) => result,
+                    Poll::Pending if *this.policy == SlowSink::Wait => return Poll::Pending,
+                    Poll::Pending if *this.body_done => {
+                        tracing::warn!("`TeeBody` sink is too slow to finish, no longer copying");
+                        this.buffer.clear();
+                        Ok(())
+                    }
+                    Poll::Pending => Ok(()),
+                };
+
+                if let Err(err) = result {
+                    if *this.policy == SlowSink::Wait {
+                        return Poll::Ready(Some(Err(Error::new(err))));
+                    }
+                    tracing::warn!(%err, "failed to write to `TeeBody` sink, no longer copying");
+                    *this.sink = None;
+                    this.buffer.clear();
+                } else if *this.body_done {
+                    *this.sink = None;
+                }
+            }
+
+            if *this.body_done {
+                return Poll::Ready(None);
+            }
+
+            match ready!(this.body.as_mut().poll_frame(cx)) {
+                Some(Ok(frame)) => {
+                    if let (Some(data), true) = (frame.data_ref(), this.sink.is_some()) {
+                        match *this.policy {
+                            SlowSink::Detach { max_buffered }
+                                if this.buffer.len() + data.len() > max_buffered =>
+                            {
+                                tracing::warn!("`TeeBody` sink is too slow, no longer copying");
+                                *this.sink = None;
+                                this.buffer.clear();
+                            }
+                            _ => this.buffer.extend_from_slice(data),
+                        }
+                    }
+                    return Poll::Ready(Some(Ok(frame)));
+                }
+                Some(Err(err)) => return Poll::Ready(Some(Err(Error::new(err)))),
+                None => *this.body_done = true,
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.sink.is_none() && self.body.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.body.size_hint()
+    }
+}
+
+fn poll_write_buffer<W>(
+    sink: &mut W,
+    buffer: &mut BytesMut,
+    cx: &mut Context<'_>,
+) -> Poll<io::Result<()>>
+where
+    W: AsyncWrite + Unpin,
+{
+    while !buffer.is_empty() {
+        let n = ready!(Pin::new(&mut *sink).poll_write(cx, buffer))?;
+        if n == 0 {
+            return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+        }
+        buffer.advance(n);
+    }
+    Poll::Ready(Ok(()))
+}
+
+impl<B, W> IntoResponse for TeeBody<B, W>
+where
+    B: HttpBody<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+    W: AsyncWrite + Unpin + Send + 'static,
+{
+    fn into_response(self) -> Response {
+        Body::new(self).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::stream;
+    use http_body_util::BodyExt;
+    use std::convert::Infallible;
+
+    fn chunked(chunks: &'static [&'static str]) -> Body {
+        Body::from_stream(stream::iter(chunks.iter().map(|&c| Ok::<_, Infallible>(c))))
+    }
+
+    #[tokio::test]
+    async fn copies_body_into_sink() {
+        let mut sink = Vec::new();
+        let body = TeeBody::new(chunked(&["hello ", "world"]), &mut sink);
+
+        let bytes = body.collect().await.unwrap().to_bytes();
+
+        assert_eq!(bytes, "hello world");
+        assert_eq!(sink, b"hello world");
+    }
+
+    #[tokio::test]
+    async fn wait_aborts_on_sink_error() {
+        let (sink, reader) = tokio::io::duplex(64);
+        drop(reader);
+
+        let body = TeeBody::new(chunked(&["hello ", "world"]), sink);
+        let err = body.collect().await.unwrap_err();
+
+        assert_eq!(
+            err.into_inner().downcast::<io::Error>().unwrap().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+    }
+
+    #[tokio::test]
+    async fn detach_stops_copying_to_slow_sink() {
+        // the duplex buffer is smaller than the body and nobody reads from it, so the sink stalls
+        let (sink, _reader) = tokio::io::duplex(4);
+
+        let body = TeeBody::new(chunked(&["hello ", "world"]), sink)
+            .slow_sink(SlowSink::Detach { max_buffered: 4 });
+        let bytes = body.collect().await.unwrap().to_bytes();
+
+        assert_eq!(bytes, "hello world");
+    }
+
+    #[tokio::test]
+    async fn detach_drops_stalled_sink_at_end_of_body() {
+        // everything fits in the buffer, but the sink stalls after 4 bytes
+        let (sink, mut reader) = tokio::io::duplex(4);
+
+        let body = TeeBody::new(chunked(&["hello ", "world"]), sink)
+            .slow_sink(SlowSink::Detach { max_buffered: 1024 });
+        let bytes = body.collect().await.unwrap().to_bytes();
+
+        assert_eq!(bytes, "hello world");
+        let mut copied = Vec::new();
+        tokio::io::AsyncReadExt::read_to_end(&mut reader, &mut copied)
+            .await
+            .unwrap();
+        assert_eq!(copied, b"hell");
+    }
+}
@@ -0,0 +1,181 @@
+use crate::response::{attachment::parse_filename, Attachment};
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{FromRequest, Request},
+    RequestExt,
+};
+use http::{header, HeaderValue};
+use std::convert::Infallible;
+
+/// Extractor for raw, non-multipart file uploads.
+///
+/// This pairs the request body with the filename from the `Content-Disposition` header and the
+/// `Content-Type` header, mirroring what [`Attachment`] sends in responses.
+///
+/// ⚠️ Since extracting the upload requires consuming the body, the `UploadedAttachment` extractor
+/// must be *last* if there are multiple extractors in a handler.
+/// See ["the order of extractors"][order-of-extractors]
+///
+/// The filename is supplied by the client and must not be trusted. Use a [`FilenamePolicy`] such
+/// as [`SanitizeFilename`] before using it to store the upload.
+///
+/// [order-of-extractors]: crate::extract#the-order-of-extractors
+/// [`Attachment`]: crate::response::Attachment
+/// [`FilenamePolicy`]: crate::response::attachment::FilenamePolicy
+/// [`SanitizeFilename`]: crate::response::attachment::SanitizeFilename
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::put, Router};
+/// use axum_extra::extract::UploadedAttachment;
+///
+/// async fn upload(upload: UploadedAttachment) {
+///     let filename = upload.filename().unwrap_or("upload.bin").to_owned();
+///     let body = upload.into_body();
+///     // ...
+/// }
+///
+/// let app = Router::new().route("/upload", put(upload));
+/// # let _: Router = app;
+/// ```
+#[derive(Debug)]
+pub struct UploadedAttachment {
+    filename: Option<String>,
+    content_type: Option<HeaderValue>,
+    body: Body,
+}
+
+#[async_trait]
+impl<S> FromRequest<S> for UploadedAttachment
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request(req: Request, _state: &S) -> Result<Self, Self::Rejection> {
+        let filename = req
+            .headers()
+            .get(header::CONTENT_DISPOSITION)
+            .and_then(parse_filename);
+        let content_type = req.headers().get(header::CONTENT_TYPE).cloned();
+        let body = req.with_limited_body().into_body();
+
+        Ok(Self {
+            filename,
+            content_type,
+            body,
+        })
+    }
+}
+
+impl UploadedAttachment {
+    /// The filename from the `Content-Disposition` header, if any.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// The `Content-Type` of the upload, if any.
+    pub fn content_type(&self) -> Option<&HeaderValue> {
+        self.content_type.as_ref()
+    }
+
+    /// Consume the extractor, returning the body of the upload.
+    pub fn into_body(self) -> Body {
+        self.body
+    }
+}
+
+impl From<UploadedAttachment> for Attachment<Body> {
+    /// Serve an upload back as an [`Attachment`], keeping its filename and content-type.
+    ///
+    /// The body is streamed, not buffered. This is useful as a fixture when testing download
+    /// clients end to end.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::{body::Body, routing::put, Router};
+    /// use axum_extra::{extract::UploadedAttachment, response::Attachment};
+    ///
+    /// async fn echo(upload: UploadedAttachment) -> Attachment<Body> {
+    ///     Attachment::from(upload)
+    /// }
+    ///
+    /// let app = Router::new().route("/echo", put(echo));
+    /// # let _: Router = app;
+    /// ```
+    fn from(upload: UploadedAttachment) -> Self {
+        let mut attachment = Attachment::new(upload.body);
+        if let Some(filename) = upload.filename {
+            attachment = attachment.filename(filename);
+        }
+        if let Some(content_type) = upload.content_type {
+            attachment = attachment.content_type(content_type);
+        }
+        attachment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use axum::{routing::put, Router};
+
+    #[crate::test]
+    async fn extracts_filename_and_content_type() {
+        async fn handler(upload: UploadedAttachment) -> String {
+            format!(
+                "{:?} {:?}",
+                upload.filename(),
+                upload
+                    .content_type()
+                    .map(|v| v.to_str().unwrap().to_owned())
+            )
+        }
+
+        let client = TestClient::new(Router::new().route("/", put(handler)));
+
+        let res = client
+            .put("/")
+            .header(
+                header::CONTENT_DISPOSITION,
+                "attachment; filename*=UTF-8''%E2%82%AC.txt",
+            )
+            .header(header::CONTENT_TYPE, "text/plain")
+            .body("hello")
+            .await;
+        assert_eq!(res.text().await, r#"Some("€.txt") Some("text/plain")"#);
+
+        let res = client.put("/").body("hello").await;
+        assert_eq!(res.text().await, "None None");
+    }
+
+    #[crate::test]
+    async fn echoes_upload_as_attachment() {
+        async fn echo(upload: UploadedAttachment) -> Attachment<Body> {
+            Attachment::from(upload)
+        }
+
+        let client = TestClient::new(Router::new().route("/", put(echo)));
+
+        let res = client
+            .put("/")
+            .header(
+                header::CONTENT_DISPOSITION,
+                "attachment; filename*=UTF-8''%E2%82%AC.csv",
+            )
+            .header(header::CONTENT_TYPE, "text/csv")
+            .body("a,b\n")
+            .await;
+
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"_.csv\"; filename*=UTF-8''%E2%82%AC.csv"
+        );
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/csv");
+        assert_eq!(res.text().await, "a,b\n");
+    }
+}
@@ -10,6 +10,15 @@
 //! Name | Description | Default?
 //! ---|---|---
 //! `async-read-body` | Enables the `AsyncReadBody` body | No
+//! `attachment` | Enables the `Attachment` response | No
+//! `attachment-config` | Enables `AttachmentConfig`, `Attachment::with_config` and `AttachmentDefaultsLayer` | No
+//! `attachment-deadline` | Enables the `Attachment::deadline` method | No
+//! `attachment-digest` | Enables SHA-256 based `ETag` helpers, the `Manifest` response and `SizedStream::verify_digest` | No
+//! `attachment-json` | Enables the `Attachment::json` and `Attachment::json_pretty` constructors | No
+//! `attachment-serve-file` | Enables the `ServeFileAsAttachment` service | No
+//! `attachment-tee` | Enables the `TeeBody` body | No
+//! `attachment-toml` | Enables the `Attachment::toml` constructor | No
+//! `attachment-yaml` | Enables the `Attachment::yaml` constructor | No
 //! `cookie` | Enables the `CookieJar` extractor | No
 //! `cookie-private` | Enables the `PrivateCookieJar` extractor | No
 //! `cookie-signed` | Enables the `SignedCookieJar` extractor | No
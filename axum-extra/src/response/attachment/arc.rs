@@ -0,0 +1,145 @@
+use super::{Attachment, AttachmentInfo};
+use axum::{
+    body::{Body, Bytes},
+    response::{IntoResponse, Response},
+};
+use http::{HeaderMap, Request, StatusCode};
+use std::{
+    convert::Infallible,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_service::Service;
+
+/// A prebuilt [`Attachment`] that can be served many times.
+///
+/// The headers are built once when the `ArcAttachment` is created, and the body is shared between
+/// responses, so serving it only copies the header map. This is useful for small attachments that
+/// are served at high rates, such as a logo or a template.
+///
+/// `ArcAttachment` is cheap to clone, and can be returned from a handler or used as a service.
+///
+/// Since the response is built up front, defaults from an
+/// [`AttachmentDefaultsLayer`](super::AttachmentDefaultsLayer) only apply if they are in effect
+/// where the `ArcAttachment` is created, not where it is served. Settings that wrap the body, such
+/// as [`Attachment::deadline`] and [`Attachment::sniff_content`], aren't applied.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::Router;
+/// use axum_extra::response::{attachment::ArcAttachment, Attachment};
+///
+/// let template = ArcAttachment::new(
+///     Attachment::new("id,name,email\n")
+///         .filename("import-template.csv")
+///         .content_type("text/csv"),
+/// );
+///
+/// let app = Router::new().route_service("/import-template.csv", template);
+/// # let _: Router = app;
+/// ```
+#[derive(Debug, Clone)]
+pub struct ArcAttachment(Arc<Prebuilt>);
+
+#[derive(Debug)]
+struct Prebuilt {
+    status: StatusCode,
+    headers: HeaderMap,
+    info: Option<AttachmentInfo>,
+    body: Bytes,
+}
+
+impl ArcAttachment {
+    /// Build the response for `attachment`.
+    pub fn new<T: Into<Bytes>>(attachment: Attachment<T>) -> Self {
+        let (response, body) = match attachment.into_parts() {
+            Ok((body, finish)) => {
+                let body = body.into();
+                let finish = finish.without_body_wrappers();
+                (finish.apply(Response::new(Body::from(body.clone()))), body)
+            }
+            Err(response) => (*response, Bytes::new()),
+        };
+
+        let (mut parts, _) = response.into_parts();
+        Self(Arc::new(Prebuilt {
+            status: parts.status,
+            info: parts.extensions.remove(),
+            headers: parts.headers,
+            body,
+        }))
+    }
+}
+
+impl IntoResponse for ArcAttachment {
+    fn into_response(self) -> Response {
+        let mut response = Response::new(Body::from(self.0.body.clone()));
+        *response.status_mut() = self.0.status;
+        *response.headers_mut() = self.0.headers.clone();
+        if let Some(info) = &self.0.info {
+            response.extensions_mut().insert(info.clone());
+        }
+        response
+    }
+}
+
+impl<B> Service<Request<B>> for ArcAttachment {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = std::future::Ready<Result<Response, Infallible>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, _req: Request<B>) -> Self::Future {
+        std::future::ready(Ok(self.clone().into_response()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use axum::{routing::get, Router};
+    use http::header;
+
+    #[crate::test]
+    async fn serves_prebuilt_response() {
+        let attachment = ArcAttachment::new(
+            Attachment::new("id,name\n")
+                .filename("template.csv")
+                .content_type("text/csv")
+                .nosniff(),
+        );
+
+        let app = Router::new()
+            .route_service("/", attachment.clone())
+            .route("/handler", get(move || async move { attachment }));
+        let client = TestClient::new(app);
+
+        for path in ["/", "/", "/handler"] {
+            let res = client.get(path).await;
+            assert_eq!(res.status(), StatusCode::OK);
+            assert_eq!(
+                res.headers()[header::CONTENT_DISPOSITION],
+                "attachment; filename=\"template.csv\""
+            );
+            assert_eq!(res.headers()[header::CONTENT_TYPE], "text/csv");
+            assert_eq!(res.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+            assert_eq!(res.text().await, "id,name\n");
+        }
+    }
+
+    #[test]
+    fn keeps_attachment_info() {
+        let response =
+            ArcAttachment::new(Attachment::new("hello").filename("hello.txt")).into_response();
+
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.filename(), Some("hello.txt"));
+        assert_eq!(info.size(), Some(5));
+    }
+}
@@ -0,0 +1,138 @@
+use super::{filename::DEFAULT_EXTENSIONS, ContentSniffing, ExtensionMismatch};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// Default settings for [`Attachment`] responses.
+///
+/// This can be deserialized from application configuration and applied with
+/// [`Attachment::with_config`], or to every attachment created by a router with
+/// [`AttachmentDefaultsLayer`](super::AttachmentDefaultsLayer). Missing fields take their default
+/// values.
+///
+/// There are no chunk size or compression settings, since `Attachment` sends the body it wraps as
+/// is. Use [`CompressionLayer`] from `tower-http` to compress responses.
+///
+/// [`CompressionLayer`]: https://docs.rs/tower-http/latest/tower_http/compression/struct.CompressionLayer.html
+///
+/// # Example
+///
+/// ```rust
+/// use axum_extra::response::{attachment::AttachmentConfig, Attachment};
+///
+/// let config: AttachmentConfig = serde_json::from_str(r#"{
+///     "cache_control": "private, max-age=3600",
+///     "nosniff": true,
+///     "sanitize_filenames": true
+/// }"#).unwrap();
+///
+/// let attachment = Attachment::new("...")
+///     .filename("report.csv")
+///     .with_config(&config);
+/// # let _ = attachment;
+/// ```
+///
+/// [`Attachment`]: super::Attachment
+/// [`Attachment::with_config`]: super::Attachment::with_config
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+#[non_exhaustive]
+pub struct AttachmentConfig {
+    /// The `Cache-Control` header to send, if any.
+    pub cache_control: Option<String>,
+    /// Whether to send `X-Content-Type-Options: nosniff`.
+    pub nosniff: bool,
+    /// Whether to list `Content-Disposition` in `Access-Control-Expose-Headers`.
+    pub expose_content_disposition: bool,
+    /// Whether to harden HTML and SVG attachments, see
+    /// [`Attachment::harden_active_content`](super::Attachment::harden_active_content).
+    pub harden_active_content: bool,
+    /// Whether to apply [`SanitizeFilename`](super::SanitizeFilename) to filenames.
+    pub sanitize_filenames: bool,
+    /// The maximum length of filenames in bytes.
+    pub max_filename_len: usize,
+    /// Extensions, without a leading `.`, appended to filenames without an extension, by content
+    /// type.
+    ///
+    /// Content types are matched without parameters and case-insensitively, so `text/csv` also
+    /// matches `text/CSV; charset=utf-8`. Empty by default, use
+    /// [`AttachmentConfig::with_common_extensions`] for a map of common content types, such as
+    /// `text/csv` to `csv` and `application/pdf` to `pdf`.
+    pub default_extensions: HashMap<String, String>,
+    /// What to do when the extension of a filename doesn't match the content type.
+    pub extension_mismatch: ExtensionMismatch,
+    /// What to do when the start of the body contradicts the content type.
+    pub content_sniffing: ContentSniffing,
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        Self {
+            cache_control: None,
+            nosniff: false,
+            expose_content_disposition: false,
+            harden_active_content: false,
+            sanitize_filenames: false,
+            max_filename_len: super::DEFAULT_MAX_FILENAME_LEN,
+            default_extensions: HashMap::new(),
+            extension_mismatch: ExtensionMismatch::default(),
+            content_sniffing: ContentSniffing::default(),
+        }
+    }
+}
+
+impl AttachmentConfig {
+    /// Create a default config whose [`default_extensions`](Self::default_extensions) map common
+    /// content types to their extensions.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::response::IntoResponse;
+    /// use axum_extra::response::{attachment::AttachmentConfig, Attachment};
+    ///
+    /// let response = Attachment::new("id,name\n")
+    ///     .filename("report")
+    ///     .content_type("text/csv")
+    ///     .with_config(&AttachmentConfig::with_common_extensions())
+    ///     .into_response();
+    /// assert_eq!(response.headers()["content-disposition"], "attachment; filename=\"report.csv\"");
+    /// ```
+    pub fn with_common_extensions() -> Self {
+        Self {
+            default_extensions: DEFAULT_EXTENSIONS
+                .iter()
+                .map(|&(content_type, extension)| (content_type.to_owned(), extension.to_owned()))
+                .collect(),
+            ..Self::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_fields_use_defaults() {
+        let config: AttachmentConfig = serde_json::from_str(r#"{ "nosniff": true }"#).unwrap();
+
+        assert!(config.nosniff);
+        assert!(config.cache_control.is_none());
+        assert!(!config.sanitize_filenames);
+        assert_eq!(config.max_filename_len, 255);
+        assert!(config.default_extensions.is_empty());
+        assert_eq!(config.extension_mismatch, ExtensionMismatch::Allow);
+
+        let config: AttachmentConfig =
+            serde_json::from_str(r#"{ "extension_mismatch": "reject" }"#).unwrap();
+        assert_eq!(config.extension_mismatch, ExtensionMismatch::Reject);
+    }
+
+    #[test]
+    fn common_extensions() {
+        let config = AttachmentConfig::with_common_extensions();
+
+        assert_eq!(config.default_extensions["text/csv"], "csv");
+        assert_eq!(config.default_extensions["application/pdf"], "pdf");
+    }
+}
@@ -0,0 +1,141 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    BoxError, Error,
+};
+use futures_util::ready;
+use http_body::{Frame, SizeHint};
+use pin_project_lite::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+use tokio::time::{sleep, Sleep};
+
+pin_project! {
+    /// Body that fails once `deadline` has passed since it was first polled.
+    pub(super) struct DeadlineBody {
+        #[pin]
+        inner: Body,
+        deadline: Duration,
+        sleep: Option<Pin<Box<Sleep>>>,
+        sent: u64,
+        expired: bool,
+    }
+}
+
+impl DeadlineBody {
+    pub(super) fn new(inner: Body, deadline: Duration) -> Self {
+        Self {
+            inner,
+            deadline,
+            sleep: None,
+            sent: 0,
+            expired: false,
+        }
+    }
+}
+
+impl HttpBody for DeadlineBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+
+        if *this.expired {
+            return Poll::Ready(None);
+        }
+
+        let deadline = *this.deadline;
+        let sleep = this.sleep.get_or_insert_with(|| Box::pin(sleep(deadline)));
+        if sleep.as_mut().poll(cx).is_ready() {
+            *this.expired = true;
+            tracing::warn!(
+                deadline = ?this.deadline,
+                sent = *this.sent,
+                "attachment download exceeded its deadline, aborting",
+            );
+            return Poll::Ready(Some(Err(Box::new(DeadlineExceeded {
+                deadline: *this.deadline,
+            }))));
+        }
+
+        let frame = ready!(this.inner.poll_frame(cx));
+        if let Some(data) = frame.as_ref().and_then(|f| f.as_ref().ok()?.data_ref()) {
+            *this.sent += data.len() as u64;
+        }
+        Poll::Ready(frame.map(|frame| frame.map_err(Error::into_inner)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.expired || self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+/// Error produced when an attachment is not sent completely within its
+/// [deadline](super::Attachment::deadline).
+///
+/// This is wrapped in an [`axum::Error`] and can be recovered with
+/// [`into_inner`](axum::Error::into_inner) and [`downcast`](std::error::Error#method.downcast).
+#[derive(Debug)]
+pub struct DeadlineExceeded {
+    deadline: Duration,
+}
+
+impl DeadlineExceeded {
+    /// The deadline that was exceeded.
+    pub fn deadline(&self) -> Duration {
+        self.deadline
+    }
+}
+
+impl fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "download not completed within {:?}", self.deadline)
+    }
+}
+
+impl std::error::Error for DeadlineExceeded {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::Attachment;
+    use axum::response::IntoResponse;
+    use futures_util::stream;
+    use http_body_util::BodyExt;
+    use std::convert::Infallible;
+
+    #[tokio::test(start_paused = true)]
+    async fn aborts_after_deadline() {
+        let body = Body::from_stream(stream::pending::<Result<Bytes, Infallible>>());
+        let response = Attachment::new(body)
+            .deadline(Duration::from_secs(10))
+            .into_response();
+
+        let err = response.into_body().collect().await.unwrap_err();
+        let err = err.into_inner().downcast::<DeadlineExceeded>().unwrap();
+        assert_eq!(err.deadline(), Duration::from_secs(10));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn completes_within_deadline() {
+        let response = Attachment::new("hello")
+            .deadline(Duration::from_secs(10))
+            .into_response();
+
+        assert_eq!(response.body().size_hint().exact(), Some(5));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "hello");
+    }
+}
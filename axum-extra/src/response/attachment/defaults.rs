@@ -0,0 +1,139 @@
+use super::AttachmentConfig;
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::task::futures::TaskLocalFuture;
+use tower_layer::Layer;
+use tower_service::Service;
+
+tokio::task_local! {
+    static DEFAULTS: Arc<AttachmentConfig>;
+}
+
+/// The defaults set by the innermost [`AttachmentDefaultsLayer`], if any.
+pub(super) fn current() -> Option<Arc<AttachmentConfig>> {
+    DEFAULTS.try_with(Arc::clone).ok()
+}
+
+/// Layer that applies an [`AttachmentConfig`] to every [`Attachment`] created by the services it
+/// wraps.
+///
+/// Settings made directly on an [`Attachment`] take precedence over these defaults. To apply
+/// defaults process-wide, add the layer to the outermost [`Router`](axum::Router).
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_extra::response::{
+///     attachment::{AttachmentConfig, AttachmentDefaultsLayer},
+///     Attachment,
+/// };
+///
+/// let mut defaults = AttachmentConfig::default();
+/// defaults.nosniff = true;
+/// defaults.expose_content_disposition = true;
+///
+/// let app = Router::new()
+///     .route("/report.csv", get(|| async { Attachment::new("...").filename("report.csv") }))
+///     .layer(AttachmentDefaultsLayer::new(defaults));
+/// # let _: Router = app;
+/// ```
+///
+/// [`Attachment`]: super::Attachment
+#[derive(Debug, Clone)]
+pub struct AttachmentDefaultsLayer {
+    config: Arc<AttachmentConfig>,
+}
+
+impl AttachmentDefaultsLayer {
+    /// Create a new `AttachmentDefaultsLayer`.
+    pub fn new(config: AttachmentConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl<S> Layer<S> for AttachmentDefaultsLayer {
+    type Service = AttachmentDefaults<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AttachmentDefaults {
+            inner,
+            config: Arc::clone(&self.config),
+        }
+    }
+}
+
+/// Middleware that applies an [`AttachmentConfig`] to attachments.
+///
+/// See [`AttachmentDefaultsLayer`] for more details.
+#[derive(Debug, Clone)]
+pub struct AttachmentDefaults<S> {
+    inner: S,
+    config: Arc<AttachmentConfig>,
+}
+
+impl<S, R> Service<R> for AttachmentDefaults<S>
+where
+    S: Service<R>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = TaskLocalFuture<Arc<AttachmentConfig>, S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: R) -> Self::Future {
+        DEFAULTS.scope(Arc::clone(&self.config), self.inner.call(req))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{response::Attachment, test_helpers::*};
+    use axum::{routing::get, Router};
+    use http::header;
+
+    #[crate::test]
+    async fn applies_defaults_to_attachments() {
+        let defaults = AttachmentConfig {
+            nosniff: true,
+            cache_control: Some("no-store".to_owned()),
+            expose_content_disposition: true,
+            ..Default::default()
+        };
+
+        let app = Router::new()
+            .route("/", get(|| async { Attachment::new("hello") }))
+            .route(
+                "/cached",
+                get(|| async { Attachment::new("hello").cache_control("max-age=60") }),
+            )
+            .layer(AttachmentDefaultsLayer::new(defaults));
+
+        let client = TestClient::new(app);
+
+        let res = client.get("/").await;
+        assert_eq!(res.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+        assert_eq!(res.headers()[header::CACHE_CONTROL], "no-store");
+        assert_eq!(
+            res.headers()[header::ACCESS_CONTROL_EXPOSE_HEADERS],
+            "content-disposition"
+        );
+
+        let res = client.get("/cached").await;
+        assert_eq!(res.headers()[header::CACHE_CONTROL], "max-age=60");
+    }
+
+    #[test]
+    fn no_defaults_outside_layer() {
+        assert!(current().is_none());
+    }
+}
@@ -0,0 +1,162 @@
+use super::unsatisfied_content_range;
+use axum::{
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use http::{header, StatusCode};
+use std::{fmt, io};
+
+/// Errors that can happen while serving an attachment.
+///
+/// This implements [`IntoResponse`] with a matching status code, so handlers serving files can
+/// use `?`. The response body is a generic message that never includes paths or the underlying
+/// error; source errors are logged instead.
+///
+/// [`io::Error`]s convert into `NotFound`, `PermissionDenied` or `Source` depending on their
+/// kind.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{extract::Path, routing::get, Router};
+/// use axum_extra::response::{attachment::AttachmentError, Attachment};
+///
+/// async fn download(Path(name): Path<String>) -> Result<Attachment<Vec<u8>>, AttachmentError> {
+///     if name.contains('/') {
+///         return Err(AttachmentError::InvalidFilename);
+///     }
+///     let contents = tokio::fs::read(format!("downloads/{name}")).await?;
+///     Ok(Attachment::new(contents).filename(name))
+/// }
+///
+/// let app = Router::new().route("/downloads/:name", get(download));
+/// # let _: Router = app;
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum AttachmentError {
+    /// The file doesn't exist. Responds with `404 Not Found`.
+    NotFound,
+    /// The file can't be accessed. Responds with `403 Forbidden`.
+    PermissionDenied,
+    /// None of the requested ranges could be served. Responds with
+    /// `416 Range Not Satisfiable` and a `Content-Range` header.
+    RangeNotSatisfiable {
+        /// The length of the file in bytes.
+        len: u64,
+    },
+    /// The requested filename or path isn't valid. Responds with `400 Bad Request`.
+    InvalidFilename,
+    /// Reading the file failed. Responds with `500 Internal Server Error`.
+    Source(BoxError),
+}
+
+impl AttachmentError {
+    /// Create an `AttachmentError::Source` from any error.
+    pub fn source<E>(error: E) -> Self
+    where
+        E: Into<BoxError>,
+    {
+        Self::Source(error.into())
+    }
+
+    /// The status code of the response for this error.
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::NotFound => StatusCode::NOT_FOUND,
+            Self::PermissionDenied => StatusCode::FORBIDDEN,
+            Self::RangeNotSatisfiable { .. } => StatusCode::RANGE_NOT_SATISFIABLE,
+            Self::InvalidFilename => StatusCode::BAD_REQUEST,
+            Self::Source(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl From<io::Error> for AttachmentError {
+    fn from(error: io::Error) -> Self {
+        match error.kind() {
+            io::ErrorKind::NotFound => Self::NotFound,
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied,
+            _ => Self::Source(error.into()),
+        }
+    }
+}
+
+impl fmt::Display for AttachmentError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => f.write_str("File not found"),
+            Self::PermissionDenied => f.write_str("Permission denied"),
+            Self::RangeNotSatisfiable { .. } => f.write_str("Requested range not satisfiable"),
+            Self::InvalidFilename => f.write_str("Invalid filename"),
+            Self::Source(error) => write!(f, "Failed to read file: {error}"),
+        }
+    }
+}
+
+impl std::error::Error for AttachmentError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Source(error) => Some(&**error),
+            _ => None,
+        }
+    }
+}
+
+impl IntoResponse for AttachmentError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+        match self {
+            Self::RangeNotSatisfiable { len } => (
+                status,
+                [(header::CONTENT_RANGE, unsatisfied_content_range(len))],
+                "Requested range not satisfiable",
+            )
+                .into_response(),
+            Self::Source(error) => {
+                tracing::error!(%error, "failed to serve attachment");
+                (status, "Internal server error").into_response()
+            }
+            error => (status, error.to_string()).into_response(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    #[test]
+    fn io_errors_map_to_status_codes() {
+        let error = AttachmentError::from(io::Error::from(io::ErrorKind::NotFound));
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+
+        let error = AttachmentError::from(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+
+        let error = AttachmentError::from(io::Error::from(io::ErrorKind::UnexpectedEof));
+        assert_eq!(error.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[tokio::test]
+    async fn source_errors_are_redacted() {
+        let error = AttachmentError::source(io::Error::new(
+            io::ErrorKind::Other,
+            "/srv/secret/path is broken",
+        ));
+        let response = error.into_response();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "Internal server error");
+    }
+
+    #[test]
+    fn range_not_satisfiable_sets_content_range() {
+        let response = AttachmentError::RangeNotSatisfiable { len: 100 }.into_response();
+
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */100");
+    }
+}
@@ -0,0 +1,118 @@
+use http::HeaderValue;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "attachment-digest")]
+use sha2::{Digest, Sha256};
+#[cfg(feature = "attachment-digest")]
+use std::fmt::Write;
+
+/// Compute a weak `ETag` from the length and modification time of a resource.
+///
+/// This is cheap and suitable for files, where the metadata changes whenever the contents do.
+/// Because different contents could in theory share the same metadata the tag is marked weak.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_extra::response::attachment::etag_from_metadata;
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let mtime = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+/// assert_eq!(etag_from_metadata(1024, mtime), r#"W/"400-6553f100""#);
+/// ```
+pub fn etag_from_metadata(len: u64, mtime: SystemTime) -> HeaderValue {
+    let value = match mtime.duration_since(UNIX_EPOCH) {
+        Ok(since_epoch) if since_epoch.subsec_nanos() == 0 => {
+            format!("W/\"{:x}-{:x}\"", len, since_epoch.as_secs())
+        }
+        Ok(since_epoch) => format!(
+            "W/\"{:x}-{:x}.{:x}\"",
+            len,
+            since_epoch.as_secs(),
+            since_epoch.subsec_nanos()
+        ),
+        // modification times before the epoch are rare enough that the length will do
+        Err(_) => format!("W/\"{len:x}\""),
+    };
+    HeaderValue::try_from(value).expect("formatted integers are a valid header value")
+}
+
+/// Compute a strong `ETag` from the SHA-256 digest of `bytes`.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_extra::response::attachment::etag_from_bytes;
+///
+/// assert_eq!(
+///     etag_from_bytes(b"hello"),
+///     r#""2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824""#,
+/// );
+/// ```
+#[cfg(feature = "attachment-digest")]
+pub fn etag_from_bytes(bytes: &[u8]) -> HeaderValue {
+    strong_etag(Sha256::digest(bytes).as_slice())
+}
+
+/// Compute a strong `ETag` from the SHA-256 digest of everything read from `reader`.
+///
+/// The contents are hashed as they are read, so this never buffers more than a small chunk.
+///
+/// # Errors
+///
+/// Returns any error produced while reading.
+#[cfg(feature = "attachment-digest")]
+pub async fn etag_from_reader<R>(mut reader: R) -> std::io::Result<HeaderValue>
+where
+    R: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 8 * 1024];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(strong_etag(hasher.finalize().as_slice()))
+}
+
+#[cfg(feature = "attachment-digest")]
+fn strong_etag(digest: &[u8]) -> HeaderValue {
+    let mut value = String::with_capacity(digest.len() * 2 + 2);
+    value.push('"');
+    for byte in digest {
+        let _ = write!(value, "{byte:02x}");
+    }
+    value.push('"');
+    HeaderValue::try_from(value).expect("hex digits are a valid header value")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn metadata_etag_includes_subsec_nanos() {
+        let mtime = UNIX_EPOCH + Duration::new(16, 255);
+        assert_eq!(etag_from_metadata(17, mtime), r#"W/"11-10.ff""#);
+    }
+
+    #[test]
+    fn metadata_etag_changes_with_len() {
+        let mtime = UNIX_EPOCH + Duration::from_secs(60);
+        assert_ne!(etag_from_metadata(1, mtime), etag_from_metadata(2, mtime));
+    }
+
+    #[cfg(feature = "attachment-digest")]
+    #[tokio::test]
+    async fn reader_and_bytes_agree() {
+        let bytes = vec![7; 20 * 1024];
+        let from_reader = etag_from_reader(&bytes[..]).await.unwrap();
+        assert_eq!(from_reader, etag_from_bytes(&bytes));
+    }
+}
@@ -0,0 +1,859 @@
+use bytes::Bytes;
+use http::HeaderValue;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+#[cfg(feature = "attachment-config")]
+use serde::Deserialize;
+use std::{borrow::Cow, collections::HashMap, fmt, path::Path, sync::Arc};
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+/// Characters that don't need to be percent-encoded in an RFC 8187 `ext-value`.
+const ATTR_CHAR: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'!')
+    .remove(b'#')
+    .remove(b'$')
+    .remove(b'&')
+    .remove(b'+')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'^')
+    .remove(b'_')
+    .remove(b'`')
+    .remove(b'|')
+    .remove(b'~');
+
+/// A policy applied to the filename of an [`Attachment`] before it is sent.
+///
+/// Implement this to encode organization-wide filename rules, such as sanitizing, transliterating
+/// or enforcing an extension, once instead of at every call to [`Attachment::filename`]. Closures
+/// taking a `&str` and returning a `String` implement this trait as well.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_extra::response::{attachment::FilenamePolicy, Attachment};
+/// use std::borrow::Cow;
+///
+/// struct Lowercase;
+///
+/// impl FilenamePolicy for Lowercase {
+///     fn apply<'a>(&self, filename: &'a str) -> Cow<'a, str> {
+///         Cow::Owned(filename.to_lowercase())
+///     }
+/// }
+///
+/// let attachment = Attachment::new("...")
+///     .filename("Report.CSV")
+///     .filename_policy(Lowercase);
+/// # let _ = attachment;
+/// ```
+///
+/// [`Attachment`]: super::Attachment
+/// [`Attachment::filename`]: super::Attachment::filename
+pub trait FilenamePolicy: Send + Sync + 'static {
+    /// Apply the policy to `filename`.
+    ///
+    /// Returning an empty filename omits the filename from the response.
+    fn apply<'a>(&self, filename: &'a str) -> Cow<'a, str>;
+}
+
+impl<F> FilenamePolicy for F
+where
+    F: Fn(&str) -> String + Send + Sync + 'static,
+{
+    fn apply<'a>(&self, filename: &'a str) -> Cow<'a, str> {
+        Cow::Owned(self(filename))
+    }
+}
+
+/// A [`FilenamePolicy`] that makes filenames safe to save on any common platform.
+///
+/// Path separators, control characters and characters reserved on Windows (`<>:"/\|?*`) are
+/// replaced with `_`, and trailing dots and spaces, which Windows strips, are removed.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::response::IntoResponse;
+/// use axum_extra::response::{attachment::SanitizeFilename, Attachment};
+///
+/// let response = Attachment::new("...")
+///     .filename("../etc/passwd")
+///     .filename_policy(SanitizeFilename)
+///     .into_response();
+/// assert_eq!(response.headers()["content-disposition"], "attachment; filename=\".._etc_passwd\"");
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SanitizeFilename;
+
+impl FilenamePolicy for SanitizeFilename {
+    fn apply<'a>(&self, filename: &'a str) -> Cow<'a, str> {
+        fn is_unsafe(c: char) -> bool {
+            c.is_control() || matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*')
+        }
+
+        let trimmed = filename.trim_end_matches(['.', ' ']);
+        if !trimmed.contains(is_unsafe) {
+            return Cow::Borrowed(trimmed);
+        }
+
+        Cow::Owned(
+            trimmed
+                .chars()
+                .map(|c| if is_unsafe(c) { '_' } else { c })
+                .collect(),
+        )
+    }
+}
+
+/// Normalize `filename` to Unicode Normalization Form C.
+///
+/// Filenames originating from macOS are often decomposed (NFD), which makes them byte-for-byte
+/// different from the same name typed on other platforms.
+pub(super) fn normalize(filename: &str) -> Cow<'_, str> {
+    if is_nfc_quick(filename.chars()) == IsNormalized::Yes {
+        Cow::Borrowed(filename)
+    } else {
+        Cow::Owned(filename.nfc().collect())
+    }
+}
+
+/// The final component of `path` as a filename that is safe to send.
+///
+/// Returns `None` if the path doesn't end in a file name, such as `/` or `a/..`.
+pub(super) fn from_path(path: &Path) -> Option<String> {
+    let filename = path.file_name()?.to_string_lossy();
+    Some(SanitizeFilename.apply(&filename).into_owned())
+}
+
+/// Truncate `filename` to at most `max_len` bytes, preserving the extension if possible.
+pub(super) fn truncate(filename: &str, max_len: usize) -> Cow<'_, str> {
+    if filename.len() <= max_len {
+        return Cow::Borrowed(filename);
+    }
+
+    let (stem, extension) = match filename.rfind('.') {
+        // only keep extensions that leave room for a reasonable part of the stem
+        Some(idx) if idx > 0 && filename.len() - idx <= max_len / 2 => filename.split_at(idx),
+        _ => (filename, ""),
+    };
+
+    let mut end = max_len - extension.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    Cow::Owned(format!("{}{}", &stem[..end], extension))
+}
+
+/// Append the first of `extensions` to `filename`, unless it already ends in one of them.
+///
+/// If `replace` is set, a different extension is replaced instead of kept. Returns `None` if the
+/// filename can be kept as is.
+pub(super) fn ensure_extension(
+    filename: &str,
+    extensions: &[&str],
+    replace: bool,
+) -> Option<String> {
+    let first = extensions.first()?;
+    match filename.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => {
+            if extensions.iter().any(|e| ext.eq_ignore_ascii_case(e)) {
+                None
+            } else if replace {
+                Some(format!("{stem}.{first}"))
+            } else {
+                Some(format!("{filename}.{first}"))
+            }
+        }
+        _ => Some(format!("{filename}.{first}")),
+    }
+}
+
+/// Look up the extension for `content_type` in `extensions`, ignoring parameters and case.
+pub(super) fn extension_for_content_type<'a>(
+    extensions: &'a HashMap<String, String>,
+    content_type: &HeaderValue,
+) -> Option<&'a str> {
+    let content_type = content_type.to_str().ok()?;
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    extensions
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(essence))
+        .map(|(_, extension)| extension.as_str())
+        .filter(|extension| !extension.is_empty())
+}
+
+/// Whether `filename` ends in an extension. A leading `.` doesn't start an extension.
+pub(super) fn has_extension(filename: &str) -> bool {
+    matches!(filename.rsplit_once('.'), Some((stem, ext)) if !stem.is_empty() && !ext.is_empty())
+}
+
+/// What to do when the response wrapped by an [`Attachment`] already has a `Content-Disposition`
+/// header, for example because it was proxied from an upstream server.
+///
+/// See [`Attachment::existing_disposition`] for details.
+///
+/// [`Attachment`]: super::Attachment
+/// [`Attachment::existing_disposition`]: super::Attachment::existing_disposition
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ExistingDisposition {
+    /// Replace the header with the one of the attachment.
+    #[default]
+    Override,
+    /// Keep the header of the inner response.
+    Keep,
+    /// Log an error and respond with `500 Internal Server Error` instead.
+    Error,
+}
+
+/// What to do when the extension of the filename of an [`Attachment`] doesn't match its content
+/// type.
+///
+/// See [`Attachment::extension_mismatch`] for details.
+///
+/// [`Attachment`]: super::Attachment
+/// [`Attachment::extension_mismatch`]: super::Attachment::extension_mismatch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "attachment-config",
+    derive(Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum ExtensionMismatch {
+    /// Send the attachment anyway.
+    #[default]
+    Allow,
+    /// Send the attachment anyway, and log a warning.
+    Warn,
+    /// Log an error and respond with `500 Internal Server Error` instead.
+    Reject,
+}
+
+/// Extensions of common content types.
+pub(super) const DEFAULT_EXTENSIONS: &[(&str, &str)] = &[
+    ("application/gzip", "gz"),
+    ("application/json", "json"),
+    ("application/pdf", "pdf"),
+    ("application/sql", "sql"),
+    ("application/toml", "toml"),
+    ("application/vnd.ms-excel", "xls"),
+    (
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        "xlsx",
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        "docx",
+    ),
+    ("application/x-tar", "tar"),
+    ("application/xml", "xml"),
+    ("application/yaml", "yaml"),
+    ("application/zip", "zip"),
+    ("audio/mpeg", "mp3"),
+    ("image/gif", "gif"),
+    ("image/jpeg", "jpg"),
+    ("image/png", "png"),
+    ("image/svg+xml", "svg"),
+    ("image/webp", "webp"),
+    ("text/calendar", "ics"),
+    ("text/csv", "csv"),
+    ("text/html", "html"),
+    ("text/plain", "txt"),
+    ("text/vcard", "vcf"),
+    ("video/mp4", "mp4"),
+];
+
+/// Extensions that are commonly used instead of the ones in [`DEFAULT_EXTENSIONS`].
+const EXTENSION_ALIASES: &[(&str, &str)] = &[
+    ("htm", "html"),
+    ("jpeg", "jpg"),
+    ("text", "txt"),
+    ("vcard", "vcf"),
+    ("yml", "yaml"),
+];
+
+/// If the extension of `filename` belongs to a different content type than `content_type`,
+/// returns the extension expected for `content_type`.
+///
+/// Only extensions and content types that are both known are compared.
+pub(super) fn mismatched_extension(
+    filename: &str,
+    content_type: &HeaderValue,
+) -> Option<&'static str> {
+    let (_, ext) = filename
+        .rsplit_once('.')
+        .filter(|_| has_extension(filename))?;
+    let ext = EXTENSION_ALIASES
+        .iter()
+        .find(|(alias, _)| ext.eq_ignore_ascii_case(alias))
+        .map_or(ext, |&(_, ext)| ext);
+    if !DEFAULT_EXTENSIONS
+        .iter()
+        .any(|(_, known)| ext.eq_ignore_ascii_case(known))
+    {
+        return None;
+    }
+
+    let content_type = content_type.to_str().ok()?;
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    let &(_, expected) = DEFAULT_EXTENSIONS
+        .iter()
+        .find(|(known, _)| essence.eq_ignore_ascii_case(known))?;
+
+    (!ext.eq_ignore_ascii_case(expected)).then_some(expected)
+}
+
+/// What to do when the `Content-Disposition` header of an [`Attachment`] exceeds its size limit.
+///
+/// See [`Attachment::max_header_len`] for details.
+///
+/// [`Attachment`]: super::Attachment
+/// [`Attachment::max_header_len`]: super::Attachment::max_header_len
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DispositionOverflow {
+    /// Shorten the filename until the header fits, keeping the extension if possible.
+    #[default]
+    TruncateFilename,
+    /// Drop the `filename*` parameter and only send the ASCII `filename` fallback.
+    DropExtendedFilename,
+    /// Drop the filename entirely and send a bare disposition type.
+    DropFilename,
+}
+
+/// Build a `Content-Disposition` value with the given disposition type and filename.
+pub(super) fn content_disposition(disposition: &str, filename: &str) -> HeaderValue {
+    build_content_disposition(disposition, filename, true)
+}
+
+/// Build a `Content-Disposition` value whose header line, including the name, is at most
+/// `max_len` bytes long.
+pub(super) fn content_disposition_within(
+    disposition: &str,
+    filename: &str,
+    max_len: usize,
+    overflow: DispositionOverflow,
+) -> HeaderValue {
+    let fits = |value: &HeaderValue| header_len(value) <= max_len;
+
+    let mut value = content_disposition(disposition, filename);
+    if fits(&value) {
+        return value;
+    }
+
+    match overflow {
+        DispositionOverflow::TruncateFilename => {
+            let mut filename_len = filename.len();
+            while !fits(&value) {
+                // every byte of the filename takes up at most 4 bytes of the header
+                let excess = header_len(&value) - max_len;
+                filename_len = filename_len.saturating_sub((excess + 3) / 4);
+                let truncated = truncate(filename, filename_len);
+                if truncated.is_empty() {
+                    break;
+                }
+                value = content_disposition(disposition, &truncated);
+            }
+        }
+        DispositionOverflow::DropExtendedFilename => {
+            value = build_content_disposition(disposition, filename, false);
+        }
+        DispositionOverflow::DropFilename => {}
+    }
+
+    if fits(&value) {
+        value
+    } else {
+        HeaderValue::try_from(disposition).expect("disposition types are valid header values")
+    }
+}
+
+fn header_len(value: &HeaderValue) -> usize {
+    http::header::CONTENT_DISPOSITION.as_str().len() + ": ".len() + value.len()
+}
+
+fn build_content_disposition(disposition: &str, filename: &str, ext_value: bool) -> HeaderValue {
+    // enough for ASCII filenames without escapes, which are the most common
+    let mut value = String::with_capacity(disposition.len() + filename.len() + 13);
+    value.push_str(disposition);
+    value.push_str("; filename=\"");
+    let mut needs_ext_value = false;
+    for c in filename.chars() {
+        match c {
+            '"' | '\\' => {
+                value.push('\\');
+                value.push(c);
+            }
+            c if c.is_ascii() && !c.is_ascii_control() => value.push(c),
+            _ => {
+                value.push('_');
+                needs_ext_value = true;
+            }
+        }
+    }
+    value.push('"');
+
+    if needs_ext_value && ext_value {
+        value.push_str("; filename*=UTF-8''");
+        value.extend(utf8_percent_encode(filename, ATTR_CHAR));
+    }
+
+    HeaderValue::from_maybe_shared(Bytes::from(value))
+        .expect("filename is escaped so this can not fail")
+}
+
+/// Extract the filename from a `Content-Disposition` value.
+///
+/// The `filename*` parameter takes precedence over `filename`, as required by RFC 6266.
+pub(crate) fn parse_filename(value: &HeaderValue) -> Option<String> {
+    let value = String::from_utf8_lossy(value.as_bytes());
+    let mut rest = value.split_once(';')?.1;
+
+    let mut filename = None;
+    let mut ext_filename = None;
+
+    while !rest.is_empty() {
+        // skip empty segments and parameters without a value
+        let semicolon = rest.find(';');
+        let Some(equals) = rest
+            .find('=')
+            .filter(|&eq| semicolon.map_or(true, |semi| eq < semi))
+        else {
+            rest = semicolon.map_or("", |semi| &rest[semi + 1..]);
+            continue;
+        };
+        let (name, after_name) = (&rest[..equals], &rest[equals + 1..]);
+        let name = name.trim();
+        let after_name = after_name.trim_start();
+
+        let (param, next) = if let Some(quoted) = after_name.strip_prefix('"') {
+            let (param, after_quote) = parse_quoted_string(quoted)?;
+            let after_quote = after_quote.trim_start();
+            if !after_quote.is_empty() && !after_quote.starts_with(';') {
+                return None;
+            }
+            (
+                Cow::Owned(param),
+                after_quote.strip_prefix(';').unwrap_or(""),
+            )
+        } else {
+            let (param, rest) = after_name.split_once(';').unwrap_or((after_name, ""));
+            (Cow::Borrowed(param.trim()), rest)
+        };
+        rest = next;
+
+        if name.eq_ignore_ascii_case("filename") {
+            filename = Some(param.into_owned());
+        } else if name.eq_ignore_ascii_case("filename*") {
+            ext_filename = decode_ext_value(&param);
+        }
+    }
+
+    ext_filename.or(filename)
+}
+
+/// Parse the contents of a quoted-string, after the opening quote, returning the unescaped string
+/// and the remaining input after the closing quote.
+fn parse_quoted_string(input: &str) -> Option<(String, &str)> {
+    let mut out = String::new();
+    let mut chars = input.char_indices();
+    while let Some((idx, c)) = chars.next() {
+        match c {
+            '"' => return Some((out, &input[idx + 1..])),
+            '\\' => out.push(chars.next()?.1),
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+/// Encode `value` as an [RFC 8187] `ext-value`, such as `UTF-8''na%C3%AFve.txt`.
+///
+/// This is the format of extended header parameters like `filename*`. The charset is always
+/// UTF-8, without a language tag.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_extra::response::attachment::encode_ext_value;
+///
+/// assert_eq!(encode_ext_value("naïve résumé.txt"), "UTF-8''na%C3%AFve%20r%C3%A9sum%C3%A9.txt");
+/// ```
+///
+/// [RFC 8187]: https://www.rfc-editor.org/rfc/rfc8187
+pub fn encode_ext_value(value: &str) -> String {
+    let mut encoded = String::from("UTF-8''");
+    encoded.extend(utf8_percent_encode(value, ATTR_CHAR));
+    encoded
+}
+
+/// Decode an [RFC 8187] `ext-value`, such as `UTF-8''na%C3%AFve.txt`.
+///
+/// The `UTF-8` and `ISO-8859-1` charsets are supported, and the language tag is ignored. Returns
+/// `None` if the value is malformed, uses another charset, or isn't valid in its charset.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_extra::response::attachment::decode_ext_value;
+///
+/// assert_eq!(decode_ext_value("UTF-8'en'na%C3%AFve.txt").unwrap(), "naïve.txt");
+/// assert_eq!(decode_ext_value("iso-8859-1''na%EFve.txt").unwrap(), "naïve.txt");
+/// assert_eq!(decode_ext_value("UTF-8''%FF"), None);
+/// ```
+///
+/// [RFC 8187]: https://www.rfc-editor.org/rfc/rfc8187
+pub fn decode_ext_value(value: &str) -> Option<String> {
+    let (charset, rest) = value.split_once('\'')?;
+    let (_language, encoded) = rest.split_once('\'')?;
+    let bytes = percent_decode_str(encoded);
+
+    if charset.eq_ignore_ascii_case("utf-8") {
+        bytes.decode_utf8().ok().map(Cow::into_owned)
+    } else if charset.eq_ignore_ascii_case("iso-8859-1") {
+        Some(bytes.map(char::from).collect())
+    } else {
+        None
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct SharedFilenamePolicy(pub(super) Arc<dyn FilenamePolicy>);
+
+impl fmt::Debug for SharedFilenamePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FilenamePolicy").finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_replaces_unsafe_characters() {
+        assert_eq!(SanitizeFilename.apply("../etc/passwd"), ".._etc_passwd");
+        assert_eq!(SanitizeFilename.apply("a\"b\r\n.txt"), "a_b__.txt");
+        assert_eq!(SanitizeFilename.apply("C:\\report?.csv"), "C__report_.csv");
+    }
+
+    #[test]
+    fn sanitize_trims_trailing_dots_and_spaces() {
+        assert_eq!(SanitizeFilename.apply("report.csv. . "), "report.csv");
+    }
+
+    #[test]
+    fn normalize_composes_characters() {
+        assert_eq!(
+            normalize("re\u{301}sume\u{301}.pdf"),
+            "r\u{e9}sum\u{e9}.pdf"
+        );
+        assert!(matches!(normalize("résumé.pdf"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn truncate_preserves_extension() {
+        assert_eq!(truncate("report.csv", 10), "report.csv");
+        assert_eq!(truncate("report.csv", 8), "repo.csv");
+        assert_eq!(truncate("archive.tar.gz", 10), "archive.gz");
+    }
+
+    #[test]
+    fn truncate_without_usable_extension() {
+        assert_eq!(truncate("report", 3), "rep");
+        assert_eq!(truncate(".bashrc", 4), ".bas");
+        assert_eq!(truncate("a.verylongextension", 8), "a.verylo");
+    }
+
+    #[test]
+    fn truncate_respects_char_boundaries() {
+        assert_eq!(truncate("ééé.txt", 9), "éé.txt");
+        assert_eq!(truncate("éééé.txt", 11), "ééé.txt");
+    }
+
+    #[test]
+    fn ensure_extension_appends_missing_extension() {
+        assert_eq!(ensure_extension("a.ics", &["ics"], false), None);
+        assert_eq!(ensure_extension("a.ICS", &["ics"], false), None);
+        assert_eq!(ensure_extension("a.yml", &["yaml", "yml"], false), None);
+        assert_eq!(ensure_extension("a", &["ics"], false).unwrap(), "a.ics");
+        assert_eq!(
+            ensure_extension(".ics", &["ics"], false).unwrap(),
+            ".ics.ics"
+        );
+        assert_eq!(
+            ensure_extension("a.txt", &["ics"], false).unwrap(),
+            "a.txt.ics"
+        );
+        assert_eq!(ensure_extension("a.txt", &[], false), None);
+    }
+
+    #[test]
+    fn ensure_extension_replaces_wrong_extension() {
+        assert_eq!(
+            ensure_extension("a.png", &["jpg", "jpeg"], true).unwrap(),
+            "a.jpg"
+        );
+        assert_eq!(ensure_extension("a.JPEG", &["jpg", "jpeg"], true), None);
+        assert_eq!(
+            ensure_extension("a", &["jpg", "jpeg"], true).unwrap(),
+            "a.jpg"
+        );
+    }
+
+    #[test]
+    fn extension_from_content_type() {
+        let extensions = HashMap::from([
+            ("text/csv".to_owned(), "csv".to_owned()),
+            ("Application/PDF".to_owned(), "pdf".to_owned()),
+        ]);
+        let lookup = |content_type: &'static str| {
+            extension_for_content_type(&extensions, &HeaderValue::from_static(content_type))
+        };
+
+        assert_eq!(lookup("text/csv"), Some("csv"));
+        assert_eq!(lookup("TEXT/CSV; charset=utf-8"), Some("csv"));
+        assert_eq!(lookup("application/pdf"), Some("pdf"));
+        assert_eq!(lookup("text/plain"), None);
+
+        assert!(has_extension("a.csv"));
+        assert!(!has_extension("a"));
+        assert!(!has_extension(".profile"));
+        assert!(!has_extension("a."));
+    }
+
+    #[test]
+    fn detects_mismatched_extensions() {
+        let mismatch = |filename: &str, content_type: &'static str| {
+            mismatched_extension(filename, &HeaderValue::from_static(content_type))
+        };
+
+        assert_eq!(mismatch("report.csv", "application/pdf"), Some("pdf"));
+        assert_eq!(mismatch("photo.jpeg", "image/png"), Some("png"));
+        assert_eq!(mismatch("report.csv", "text/csv; charset=utf-8"), None);
+        assert_eq!(mismatch("photo.JPEG", "image/jpeg"), None);
+        assert_eq!(mismatch("config.yml", "application/yaml"), None);
+        // unknown extensions and content types aren't checked
+        assert_eq!(mismatch("data.bin", "application/pdf"), None);
+        assert_eq!(mismatch("report.csv", "application/octet-stream"), None);
+        assert_eq!(mismatch("report", "application/pdf"), None);
+    }
+
+    #[test]
+    fn filename_from_path() {
+        assert_eq!(
+            from_path(Path::new("reports/2024/q1.csv")).unwrap(),
+            "q1.csv"
+        );
+        assert_eq!(from_path(Path::new("q1.csv")).unwrap(), "q1.csv");
+        assert_eq!(from_path(Path::new("a/b\\c:d.txt")).unwrap(), "b_c_d.txt");
+        assert_eq!(from_path(Path::new("/")), None);
+        assert_eq!(from_path(Path::new("a/..")), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_filename_from_path() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let path = Path::new(OsStr::from_bytes(b"exports/caf\xe9\xff.txt"));
+        assert_eq!(from_path(path).unwrap(), "caf\u{fffd}\u{fffd}.txt");
+        assert_eq!(
+            content_disposition("attachment", &from_path(path).unwrap()),
+            "attachment; filename=\"caf__.txt\"; filename*=UTF-8''caf%EF%BF%BD%EF%BF%BD.txt"
+        );
+    }
+
+    #[test]
+    fn content_disposition_ascii() {
+        assert_eq!(
+            content_disposition("attachment", "report.csv"),
+            "attachment; filename=\"report.csv\""
+        );
+        assert_eq!(
+            content_disposition("inline", "a\\b\".txt"),
+            "inline; filename=\"a\\\\b\\\".txt\""
+        );
+    }
+
+    #[test]
+    fn content_disposition_non_ascii() {
+        assert_eq!(
+            content_disposition("attachment", "€ rates.txt"),
+            "attachment; filename=\"_ rates.txt\"; filename*=UTF-8''%E2%82%AC%20rates.txt"
+        );
+    }
+
+    #[test]
+    fn content_disposition_within_limit() {
+        let value = content_disposition_within(
+            "attachment",
+            "report.csv",
+            100,
+            DispositionOverflow::DropFilename,
+        );
+        assert_eq!(value, "attachment; filename=\"report.csv\"");
+    }
+
+    #[test]
+    fn content_disposition_overflow() {
+        let filename = format!("{}.csv", "€".repeat(100));
+
+        let value = content_disposition_within(
+            "attachment",
+            &filename,
+            200,
+            DispositionOverflow::TruncateFilename,
+        );
+        assert!(header_len(&value) <= 200);
+        let sent = parse_filename(&value).unwrap();
+        assert!(sent.starts_with('€'));
+        assert!(sent.ends_with(".csv"));
+
+        let value = content_disposition_within(
+            "attachment",
+            &filename,
+            200,
+            DispositionOverflow::DropExtendedFilename,
+        );
+        assert_eq!(
+            value,
+            format!("attachment; filename=\"{}.csv\"", "_".repeat(100))
+        );
+
+        let value = content_disposition_within(
+            "attachment",
+            &filename,
+            100,
+            DispositionOverflow::DropExtendedFilename,
+        );
+        assert_eq!(value, "attachment");
+
+        let value = content_disposition_within(
+            "attachment",
+            &filename,
+            200,
+            DispositionOverflow::DropFilename,
+        );
+        assert_eq!(value, "attachment");
+    }
+
+    fn parse(value: &'static str) -> Option<String> {
+        parse_filename(&HeaderValue::from_static(value))
+    }
+
+    #[test]
+    fn parse_quoted_and_token_filenames() {
+        assert_eq!(
+            parse("attachment; filename=\"a b.txt\"").unwrap(),
+            "a b.txt"
+        );
+        assert_eq!(parse("attachment; filename=a.txt").unwrap(), "a.txt");
+        assert_eq!(
+            parse("inline;filename = \"a\\\"b\\\\.txt\" ").unwrap(),
+            "a\"b\\.txt"
+        );
+        assert_eq!(
+            parse("form-data; name=\"file;x\"; filename=a.txt").unwrap(),
+            "a.txt"
+        );
+    }
+
+    #[test]
+    fn parse_skips_empty_and_bare_parameters() {
+        assert_eq!(parse("attachment; filename=\"a.txt\"; ").unwrap(), "a.txt");
+        assert_eq!(parse("attachment; filename=a.txt;").unwrap(), "a.txt");
+        assert_eq!(parse("attachment;; filename=a.txt").unwrap(), "a.txt");
+        assert_eq!(
+            parse("attachment; preview; filename=a.txt; download").unwrap(),
+            "a.txt"
+        );
+        assert_eq!(parse("attachment; preview"), None);
+    }
+
+    #[test]
+    fn ext_value_round_trip() {
+        let values = [
+            "",
+            "report.csv",
+            "naïve résumé.txt",
+            // astral plane characters, which are encoded as 4 bytes
+            "🦀 ferris.rs",
+            "𝄞𝕏𠜎.mid",
+            // mixed scripts, including right-to-left
+            "Ελληνικά 日本語 עברית العربية हिन्दी.pdf",
+            "e\u{301}\u{200d}\u{feff}",
+            "a\0b\r\n\t\u{7f}",
+            "%25'\"\\;,=*()<>@[]{}?/:",
+        ];
+        for value in values {
+            let encoded = encode_ext_value(value);
+            assert!(encoded.starts_with("UTF-8''"), "{encoded}");
+            assert!(
+                encoded[7..]
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || b"!#$&+-.^_`|~%".contains(&b)),
+                "{encoded}"
+            );
+            assert_eq!(decode_ext_value(&encoded).unwrap(), value);
+        }
+
+        for c in (0..=0x10ffff).filter_map(char::from_u32) {
+            let value = c.to_string();
+            assert_eq!(decode_ext_value(&encode_ext_value(&value)).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn decode_ext_value_rejects_malformed_values() {
+        assert_eq!(decode_ext_value("na%C3%AFve.txt"), None);
+        assert_eq!(decode_ext_value("UTF-8'na%C3%AFve.txt"), None);
+        assert_eq!(decode_ext_value("UTF-16''a"), None);
+        assert_eq!(decode_ext_value("UTF-8''%C3"), None);
+        assert_eq!(decode_ext_value("utf-8'en-US'a").unwrap(), "a");
+    }
+
+    #[test]
+    fn parse_prefers_ext_value() {
+        assert_eq!(
+            parse("attachment; filename*=UTF-8''na%C3%AFve.txt; filename=\"na_ve.txt\"").unwrap(),
+            "naïve.txt"
+        );
+        assert_eq!(
+            parse("attachment; filename*=iso-8859-1'en'%A3%20rates.txt").unwrap(),
+            "£ rates.txt"
+        );
+    }
+
+    #[test]
+    fn parse_falls_back_on_invalid_ext_value() {
+        assert_eq!(
+            parse("attachment; filename=\"a.txt\"; filename*=UTF-8''%FF").unwrap(),
+            "a.txt"
+        );
+    }
+
+    #[test]
+    fn parse_without_filename() {
+        assert_eq!(parse("attachment"), None);
+        assert_eq!(parse("attachment; size=10"), None);
+        assert_eq!(parse("attachment; filename=\"unterminated"), None);
+    }
+
+    #[test]
+    fn parse_round_trips_content_disposition() {
+        for filename in ["report.csv", "a\"b\\c", "€ rates.txt", "日本語.pdf"] {
+            let value = content_disposition("attachment", filename);
+            assert_eq!(parse_filename(&value).unwrap(), filename);
+        }
+    }
+
+    #[test]
+    fn sanitize_borrows_safe_names() {
+        assert!(matches!(
+            SanitizeFilename.apply("résumé.pdf"),
+            Cow::Borrowed("résumé.pdf")
+        ));
+    }
+}
@@ -0,0 +1,139 @@
+use super::Attachment;
+use axum::body::Bytes;
+
+impl Attachment<Bytes> {
+    /// Creates an image [`Attachment`] with the content type detected from the image data.
+    ///
+    /// PNG, JPEG, GIF, WebP and AVIF images are recognized by their magic bytes. The filename
+    /// defaults to `image` with the extension of the detected format, and if the filename is
+    /// changed, a mismatching extension is replaced, so a JPEG is never downloaded as `.png`.
+    /// Other data is sent as `application/octet-stream`, without changing the filename.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::{body::Bytes, routing::get, Router};
+    /// use axum_extra::response::Attachment;
+    ///
+    /// async fn avatar() -> Attachment<Bytes> {
+    ///     let image = Bytes::from_static(b"\x89PNG\r\n\x1a\n...");
+    ///     // sent as `ferris.png`
+    ///     Attachment::image(image).filename("ferris.jpg")
+    /// }
+    ///
+    /// let app = Router::new().route("/avatar", get(avatar));
+    /// # let _: Router = app;
+    /// ```
+    pub fn image(image: impl Into<Bytes>) -> Self {
+        let image = image.into();
+        let Some(format) = ImageFormat::detect(&image) else {
+            return Self::new(image).content_type(mime::APPLICATION_OCTET_STREAM.as_ref());
+        };
+
+        let mut attachment = Self::new(image)
+            .filename(format!("image.{}", format.extensions[0]))
+            .content_type(format.content_type);
+        attachment.extensions = format.extensions;
+        attachment.replace_extension = true;
+        attachment
+    }
+}
+
+pub(super) struct ImageFormat {
+    pub(super) content_type: &'static str,
+    extensions: &'static [&'static str],
+}
+
+impl ImageFormat {
+    const PNG: Self = Self {
+        content_type: "image/png",
+        extensions: &["png"],
+    };
+    const JPEG: Self = Self {
+        content_type: "image/jpeg",
+        extensions: &["jpg", "jpeg"],
+    };
+    const GIF: Self = Self {
+        content_type: "image/gif",
+        extensions: &["gif"],
+    };
+    const WEBP: Self = Self {
+        content_type: "image/webp",
+        extensions: &["webp"],
+    };
+    const AVIF: Self = Self {
+        content_type: "image/avif",
+        extensions: &["avif"],
+    };
+
+    pub(super) fn detect(data: &[u8]) -> Option<Self> {
+        if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::PNG)
+        } else if data.starts_with(b"\xff\xd8\xff") {
+            Some(Self::JPEG)
+        } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+            Some(Self::GIF)
+        } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+            Some(Self::WEBP)
+        } else if data.len() >= 12
+            && &data[4..8] == b"ftyp"
+            && matches!(&data[8..12], b"avif" | b"avis")
+        {
+            Some(Self::AVIF)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::AttachmentInfo;
+    use axum::response::IntoResponse;
+    use http::header;
+
+    fn detect(data: &[u8]) -> Option<&'static str> {
+        ImageFormat::detect(data).map(|format| format.content_type)
+    }
+
+    #[test]
+    fn detects_formats() {
+        assert_eq!(detect(b"\x89PNG\r\n\x1a\n\0\0"), Some("image/png"));
+        assert_eq!(detect(b"\xff\xd8\xff\xe0"), Some("image/jpeg"));
+        assert_eq!(detect(b"GIF89a"), Some("image/gif"));
+        assert_eq!(detect(b"RIFF\0\0\0\0WEBPVP8 "), Some("image/webp"));
+        assert_eq!(detect(b"\0\0\0\x1cftypavif"), Some("image/avif"));
+        assert_eq!(detect(b"RIFF\0\0\0\0WAVE"), None);
+        assert_eq!(detect(b""), None);
+    }
+
+    #[test]
+    fn fixes_filename_extension() {
+        let response = Attachment::image(&b"\xff\xd8\xff\xe0"[..])
+            .filename("photo.png")
+            .into_response();
+
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/jpeg");
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.filename(), Some("photo.jpg"));
+
+        let response = Attachment::image(&b"\xff\xd8\xff\xe0"[..]).into_response();
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.filename(), Some("image.jpg"));
+    }
+
+    #[test]
+    fn unknown_data() {
+        let response = Attachment::image(&b"hello"[..])
+            .filename("photo.png")
+            .into_response();
+
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/octet-stream"
+        );
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.filename(), Some("photo.png"));
+    }
+}
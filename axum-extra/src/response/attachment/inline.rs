@@ -0,0 +1,157 @@
+use axum::response::{IntoResponse, Response};
+use http::{header, HeaderValue};
+
+/// Wrapper that turns a `Content-Disposition: attachment` of the inner response into `inline`.
+///
+/// The filename and other parameters are kept, so browsers that display the content still use
+/// the filename when it's saved. This is useful when proxying upstream responses that force
+/// downloads for content that should be displayed.
+///
+/// Responses with a `sandbox` directive in their `Content-Security-Policy`, like attachments
+/// hardened with [`Attachment::harden_active_content`], are left as `attachment`, so active content
+/// that was meant to be downloaded isn't rendered by the browser.
+///
+/// [`Attachment::harden_active_content`]: super::Attachment::harden_active_content
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_extra::response::{attachment::ForceInline, Attachment};
+///
+/// async fn preview() -> ForceInline<Attachment<&'static str>> {
+///     ForceInline(Attachment::new("Hello!").filename("hello.txt"))
+/// }
+///
+/// let app = Router::new().route("/preview", get(preview));
+/// # let _: Router = app;
+/// ```
+#[derive(Clone, Copy, Debug)]
+#[must_use]
+pub struct ForceInline<T>(pub T);
+
+impl<T> IntoResponse for ForceInline<T>
+where
+    T: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        let mut response = self.0.into_response();
+
+        if is_sandboxed(&response) {
+            return response;
+        }
+
+        if let Some(value) = response.headers().get(header::CONTENT_DISPOSITION) {
+            let bytes = value.as_bytes();
+            let type_len = bytes.iter().position(|&b| b == b';').unwrap_or(bytes.len());
+            let is_attachment = std::str::from_utf8(&bytes[..type_len])
+                .map_or(false, |ty| ty.trim().eq_ignore_ascii_case("attachment"));
+            if is_attachment {
+                let mut inline = b"inline".to_vec();
+                inline.extend_from_slice(&bytes[type_len..]);
+                let inline = HeaderValue::from_bytes(&inline)
+                    .expect("This was a HeaderValue so this can not fail");
+                response
+                    .headers_mut()
+                    .insert(header::CONTENT_DISPOSITION, inline);
+            }
+        }
+
+        response
+    }
+}
+
+/// Whether the `Content-Security-Policy` of `response` has a `sandbox` directive.
+fn is_sandboxed(response: &Response) -> bool {
+    response
+        .headers()
+        .get_all(header::CONTENT_SECURITY_POLICY)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|policy| policy.split(';'))
+        .filter_map(|directive| directive.split_whitespace().next())
+        .any(|name| name.eq_ignore_ascii_case("sandbox"))
+}
+
+impl<T> From<T> for ForceInline<T> {
+    fn from(inner: T) -> Self {
+        Self(inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::Attachment;
+
+    #[test]
+    fn rewrites_attachment_to_inline() {
+        let response = ForceInline(Attachment::new("hello").filename("hello.txt")).into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "inline; filename=\"hello.txt\""
+        );
+
+        let response = ForceInline(Attachment::new("hello")).into_response();
+        assert_eq!(response.headers()[header::CONTENT_DISPOSITION], "inline");
+    }
+
+    #[test]
+    fn keeps_hardened_active_content_as_attachment() {
+        let attachment = Attachment::new("<script>alert(1)</script>")
+            .filename("upload.html")
+            .content_type("text/html")
+            .harden_active_content();
+        let response = ForceInline(attachment).into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"upload.html\""
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_SECURITY_POLICY],
+            "sandbox"
+        );
+
+        let attachment = Attachment::new("hello")
+            .filename("hello.txt")
+            .content_type("text/plain")
+            .harden_active_content();
+        let response = ForceInline(attachment).into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "inline; filename=\"hello.txt\""
+        );
+
+        let inner = (
+            [
+                (header::CONTENT_DISPOSITION, "attachment"),
+                (
+                    header::CONTENT_SECURITY_POLICY,
+                    "default-src 'none'; sandbox allow-forms",
+                ),
+            ],
+            "hello",
+        );
+        let response = ForceInline(inner).into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment"
+        );
+    }
+
+    #[test]
+    fn keeps_other_dispositions() {
+        let inner = (
+            [(header::CONTENT_DISPOSITION, "form-data; name=\"a\"")],
+            "hello",
+        );
+        let response = ForceInline(inner).into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "form-data; name=\"a\""
+        );
+
+        let response = ForceInline("hello").into_response();
+        assert!(!response.headers().contains_key(header::CONTENT_DISPOSITION));
+    }
+}
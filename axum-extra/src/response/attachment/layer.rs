@@ -0,0 +1,419 @@
+use super::{Attachment, ExistingDisposition};
+use axum::{
+    body::Body,
+    extract::Request,
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use bytes::Bytes;
+use futures_util::ready;
+use http::HeaderValue;
+use pin_project_lite::pin_project;
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tower_layer::Layer;
+use tower_service::Service;
+use tracing::error;
+
+/// Layer that turns the responses of the services it wraps into [`Attachment`]s.
+///
+/// This lets routing or middleware decide the download name, instead of the handler.
+///
+/// Only successful (2xx) responses are turned into attachments by default, so errors are displayed
+/// instead of being downloaded. Use [`include_error_responses`](Self::include_error_responses) to
+/// change this. Redirects and responses without content are never turned into attachments, unless
+/// [`include_bodyless_responses`](Self::include_bodyless_responses) is used.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{extract::Request, routing::get, Router};
+/// use axum_extra::response::attachment::AttachmentLayer;
+///
+/// let app = Router::new()
+///     .route(
+///         "/report",
+///         get(|| async { "id,name\n1,ferris\n" }).layer(AttachmentLayer::filename("report.csv")),
+///     )
+///     .route(
+///         "/exports/:name",
+///         get(|| async { "..." }).layer(AttachmentLayer::filename_from_request(|req: &Request| {
+///             req.uri().path().rsplit('/').next().map(|name| format!("{name}.txt"))
+///         })),
+///     );
+/// # let _: Router = app;
+/// ```
+#[derive(Clone)]
+#[must_use]
+pub struct AttachmentLayer {
+    filename: FilenameSource,
+    include_error_responses: bool,
+    include_bodyless_responses: bool,
+    existing_disposition: ExistingDisposition,
+}
+
+#[derive(Clone)]
+enum FilenameSource {
+    None,
+    Static(HeaderValue),
+    FromRequest(Arc<dyn Fn(&Request) -> Option<String> + Send + Sync>),
+}
+
+impl AttachmentLayer {
+    /// Create a new `AttachmentLayer` that doesn't set a filename.
+    pub fn new() -> Self {
+        Self::with_filename(FilenameSource::None)
+    }
+
+    /// Create a new `AttachmentLayer` that uses the same filename for every response.
+    pub fn filename<H: TryInto<HeaderValue>>(value: H) -> Self {
+        let filename = if let Ok(filename) = value.try_into() {
+            FilenameSource::Static(filename)
+        } else {
+            error!("Attachment filename contains invalid characters");
+            FilenameSource::None
+        };
+        Self::with_filename(filename)
+    }
+
+    /// Create a new `AttachmentLayer` that derives the filename from the request.
+    ///
+    /// If the closure returns `None`, no filename is sent.
+    pub fn filename_from_request<F>(f: F) -> Self
+    where
+        F: Fn(&Request) -> Option<String> + Send + Sync + 'static,
+    {
+        Self::with_filename(FilenameSource::FromRequest(Arc::new(f)))
+    }
+
+    /// Also turn responses that aren't successful into attachments.
+    pub fn include_error_responses(mut self) -> Self {
+        self.include_error_responses = true;
+        self
+    }
+
+    /// Also turn redirects and responses without content into attachments.
+    ///
+    /// See [`Attachment::include_bodyless_responses`] for details.
+    pub fn include_bodyless_responses(mut self) -> Self {
+        self.include_bodyless_responses = true;
+        self
+    }
+
+    /// Sets what to do if a response already has a `Content-Disposition` header.
+    ///
+    /// See [`Attachment::existing_disposition`] for details.
+    pub fn existing_disposition(mut self, existing: ExistingDisposition) -> Self {
+        self.existing_disposition = existing;
+        self
+    }
+
+    fn with_filename(filename: FilenameSource) -> Self {
+        Self {
+            filename,
+            include_error_responses: false,
+            include_bodyless_responses: false,
+            existing_disposition: ExistingDisposition::default(),
+        }
+    }
+}
+
+impl Default for AttachmentLayer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for AttachmentLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let filename = match &self.filename {
+            FilenameSource::None => None,
+            FilenameSource::Static(filename) => Some(filename),
+            FilenameSource::FromRequest(_) => None,
+        };
+        f.debug_struct("AttachmentLayer")
+            .field("filename", &filename)
+            .field("include_error_responses", &self.include_error_responses)
+            .field(
+                "include_bodyless_responses",
+                &self.include_bodyless_responses,
+            )
+            .field("existing_disposition", &self.existing_disposition)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Layer<S> for AttachmentLayer {
+    type Service = AttachmentService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AttachmentService {
+            inner,
+            layer: self.clone(),
+        }
+    }
+}
+
+/// Middleware that turns responses into [`Attachment`]s.
+///
+/// See [`AttachmentLayer`] for more details.
+#[derive(Clone, Debug)]
+pub struct AttachmentService<S> {
+    inner: S,
+    layer: AttachmentLayer,
+}
+
+impl<S, B> Service<Request> for AttachmentService<S>
+where
+    S: Service<Request, Response = http::Response<B>>,
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = ResponseFuture<S::Future>;
+
+    #[inline]
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: Request) -> Self::Future {
+        let filename = match &self.layer.filename {
+            FilenameSource::None => None,
+            FilenameSource::Static(filename) => Some(filename.clone()),
+            FilenameSource::FromRequest(f) => f(&req).and_then(|filename| {
+                let filename = HeaderValue::try_from(filename);
+                if filename.is_err() {
+                    error!("Attachment filename contains invalid characters");
+                }
+                filename.ok()
+            }),
+        };
+
+        ResponseFuture {
+            inner: self.inner.call(req),
+            filename,
+            include_error_responses: self.layer.include_error_responses,
+            include_bodyless_responses: self.layer.include_bodyless_responses,
+            existing_disposition: self.layer.existing_disposition,
+        }
+    }
+}
+
+pin_project! {
+    /// Response future for [`AttachmentService`].
+    pub struct ResponseFuture<F> {
+        #[pin]
+        inner: F,
+        filename: Option<HeaderValue>,
+        include_error_responses: bool,
+        include_bodyless_responses: bool,
+        existing_disposition: ExistingDisposition,
+    }
+}
+
+impl<F> fmt::Debug for ResponseFuture<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ResponseFuture")
+            .field("filename", &self.filename)
+            .field("include_error_responses", &self.include_error_responses)
+            .field(
+                "include_bodyless_responses",
+                &self.include_bodyless_responses,
+            )
+            .field("existing_disposition", &self.existing_disposition)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<F, B, E> Future for ResponseFuture<F>
+where
+    F: Future<Output = Result<http::Response<B>, E>>,
+    B: http_body::Body<Data = Bytes> + Send + 'static,
+    B::Error: Into<BoxError>,
+{
+    type Output = Result<Response, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let response = ready!(this.inner.poll(cx))?;
+
+        let status = response.status();
+        if !status.is_success() && !status.is_redirection() && !*this.include_error_responses {
+            return Poll::Ready(Ok(response.map(Body::new)));
+        }
+
+        let mut attachment =
+            Attachment::new(response).existing_disposition(*this.existing_disposition);
+        if let Some(filename) = this.filename.take() {
+            attachment = attachment.filename(filename);
+        }
+        if *this.include_bodyless_responses {
+            attachment = attachment.include_bodyless_responses();
+        }
+        Poll::Ready(Ok(attachment.into_response()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use axum::{extract::Path, routing::get, Router};
+    use http::{header, StatusCode};
+
+    #[crate::test]
+    async fn sets_filename() {
+        let app = Router::new()
+            .route("/", get(|| async { "hello" }))
+            .route_layer(AttachmentLayer::filename("hello.txt"));
+
+        let client = TestClient::new(app);
+        let res = client.get("/").await;
+
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"hello.txt\""
+        );
+        assert_eq!(
+            res.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(res.text().await, "hello");
+    }
+
+    #[crate::test]
+    async fn filename_from_request() {
+        let app = Router::new()
+            .route(
+                "/:name",
+                get(|Path(name): Path<String>| async move { name }),
+            )
+            .route_layer(AttachmentLayer::filename_from_request(|req: &Request| {
+                let name = req.uri().path().trim_start_matches('/');
+                (!name.is_empty()).then(|| format!("{name}.txt"))
+            }));
+
+        let client = TestClient::new(app);
+        let res = client.get("/report").await;
+
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"report.txt\""
+        );
+    }
+
+    #[crate::test]
+    async fn skips_error_responses() {
+        let handler = || async { (StatusCode::NOT_FOUND, "not found") };
+        let app = Router::new()
+            .route("/", get(handler))
+            .route_layer(AttachmentLayer::filename("hello.txt"))
+            .route(
+                "/all",
+                get(handler)
+                    .layer(AttachmentLayer::filename("hello.txt").include_error_responses()),
+            );
+
+        let client = TestClient::new(app);
+
+        let res = client.get("/").await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert!(res.headers().get(header::CONTENT_DISPOSITION).is_none());
+
+        let res = client.get("/all").await;
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"hello.txt\""
+        );
+    }
+
+    #[crate::test]
+    async fn skips_bodyless_responses() {
+        let handler = || async { StatusCode::NO_CONTENT };
+        let app = Router::new()
+            .route("/", get(handler))
+            .route_layer(AttachmentLayer::filename("hello.txt"))
+            .route(
+                "/redirect",
+                get(|| async { axum::response::Redirect::to("/") })
+                    .layer(AttachmentLayer::filename("hello.txt")),
+            )
+            .route(
+                "/all",
+                get(handler)
+                    .layer(AttachmentLayer::filename("hello.txt").include_bodyless_responses()),
+            );
+
+        let client = TestClient::new(app);
+
+        let res = client.get("/").await;
+        assert_eq!(res.status(), StatusCode::NO_CONTENT);
+        assert!(res.headers().get(header::CONTENT_DISPOSITION).is_none());
+
+        let res = client.get("/redirect").await;
+        assert_eq!(res.status(), StatusCode::SEE_OTHER);
+        assert!(res.headers().get(header::CONTENT_DISPOSITION).is_none());
+
+        let res = client.get("/all").await;
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"hello.txt\""
+        );
+    }
+
+    #[crate::test]
+    async fn keeps_existing_disposition() {
+        let handler = || async {
+            (
+                [(
+                    header::CONTENT_DISPOSITION,
+                    "inline; filename=\"upstream.txt\"",
+                )],
+                "hello",
+            )
+        };
+        let app = Router::new()
+            .route("/", get(handler))
+            .route_layer(
+                AttachmentLayer::filename("hello.txt")
+                    .existing_disposition(ExistingDisposition::Keep),
+            )
+            .route(
+                "/override",
+                get(handler).layer(AttachmentLayer::filename("hello.txt")),
+            );
+
+        let client = TestClient::new(app);
+
+        let res = client.get("/").await;
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "inline; filename=\"upstream.txt\""
+        );
+
+        let res = client.get("/override").await;
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"hello.txt\""
+        );
+    }
+
+    #[crate::test]
+    async fn without_filename() {
+        let app = Router::new()
+            .route("/", get(|| async { "hello" }))
+            .route_layer(AttachmentLayer::new());
+
+        let client = TestClient::new(app);
+        let res = client.get("/").await;
+
+        assert_eq!(res.headers()[header::CONTENT_DISPOSITION], "attachment");
+    }
+}
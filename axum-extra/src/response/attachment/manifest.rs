@@ -0,0 +1,210 @@
+use super::Attachment;
+use axum::response::{IntoResponse, Response};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+
+/// A `SHA256SUMS` manifest describing a set of downloadable files.
+///
+/// The manifest is served as an [`Attachment`] named `SHA256SUMS`, in the format understood by
+/// `sha256sum --check`, so clients can verify the files they downloaded. Use
+/// [`Manifest::into_json`] to serve it as JSON instead, which includes the file sizes.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_extra::response::attachment::Manifest;
+///
+/// async fn checksums() -> Manifest {
+///     Manifest::new()
+///         .file("report.csv", b"id,name\n1,ferris\n")
+///         .file("README.txt", b"Hello!")
+/// }
+///
+/// let app = Router::new().route("/SHA256SUMS", get(checksums));
+/// # let _: Router = app;
+/// ```
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct Manifest {
+    entries: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// Create an empty `Manifest`.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a file, hashing its contents.
+    pub fn file(self, name: impl Into<String>, contents: &[u8]) -> Self {
+        self.digest(name, contents.len() as u64, Sha256::digest(contents).into())
+    }
+
+    /// Add a file whose SHA-256 digest has already been computed.
+    pub fn digest(mut self, name: impl Into<String>, size: u64, sha256: [u8; 32]) -> Self {
+        self.entries.push(ManifestEntry {
+            name: name.into(),
+            size,
+            sha256,
+        });
+        self
+    }
+
+    /// The files in the manifest, in the order they were added.
+    pub fn entries(&self) -> &[ManifestEntry] {
+        &self.entries
+    }
+
+    /// Render the manifest as JSON.
+    ///
+    /// The files are listed in a `files` array, with their `name`, hex encoded `sha256` digest
+    /// and `size`:
+    ///
+    /// ```json
+    /// {"files":[{"name":"hello.txt","sha256":"2cf24dba...","size":5}]}
+    /// ```
+    pub fn to_json(&self) -> String {
+        let files = self
+            .entries
+            .iter()
+            .map(|entry| {
+                serde_json::json!({
+                    "name": entry.name,
+                    "size": entry.size,
+                    "sha256": hex(&entry.sha256),
+                })
+            })
+            .collect::<Vec<_>>();
+        serde_json::json!({ "files": files }).to_string()
+    }
+
+    /// Turn the manifest into a JSON [`Attachment`] named `manifest.json`.
+    ///
+    /// See [`Manifest::to_json`] for the format.
+    pub fn into_json(self) -> Attachment<String> {
+        Attachment::new(self.to_json())
+            .filename("manifest.json")
+            .content_type(mime::APPLICATION_JSON.as_ref())
+    }
+
+    /// Render the manifest in `SHA256SUMS` format.
+    ///
+    /// Names containing a backslash or newline are escaped the same way `sha256sum` does.
+    pub fn to_sha256sums(&self) -> String {
+        let mut out = String::new();
+        for entry in &self.entries {
+            let escape = entry.name.contains(['\\', '\n']);
+            if escape {
+                out.push('\\');
+            }
+            out.push_str(&hex(&entry.sha256));
+            out.push_str("  ");
+            if escape {
+                out.push_str(&entry.name.replace('\\', "\\\\").replace('\n', "\\n"));
+            } else {
+                out.push_str(&entry.name);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn hex(digest: &[u8]) -> String {
+    let mut out = String::with_capacity(digest.len() * 2);
+    for byte in digest {
+        let _ = write!(out, "{byte:02x}");
+    }
+    out
+}
+
+impl IntoResponse for Manifest {
+    fn into_response(self) -> Response {
+        Attachment::new(self.to_sha256sums())
+            .filename("SHA256SUMS")
+            .content_type(mime::TEXT_PLAIN_UTF_8.as_ref())
+            .into_response()
+    }
+}
+
+/// A single file in a [`Manifest`].
+#[derive(Debug, Clone)]
+pub struct ManifestEntry {
+    name: String,
+    size: u64,
+    sha256: [u8; 32],
+}
+
+impl ManifestEntry {
+    /// The name of the file.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The size of the file in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// The SHA-256 digest of the file.
+    pub fn sha256(&self) -> &[u8; 32] {
+        &self.sha256
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header;
+
+    #[test]
+    fn sha256sums_format() {
+        let manifest = Manifest::new().file("hello.txt", b"hello").file("", b"");
+
+        assert_eq!(
+            manifest.to_sha256sums(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824  hello.txt\n\
+             e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  \n"
+        );
+        assert_eq!(manifest.entries()[0].size(), 5);
+    }
+
+    #[test]
+    fn escapes_names_like_sha256sum() {
+        let manifest = Manifest::new().digest("a\\b\nc", 0, [0; 32]);
+
+        assert_eq!(
+            manifest.to_sha256sums(),
+            format!("\\{}  a\\\\b\\nc\n", "0".repeat(64))
+        );
+    }
+
+    #[test]
+    fn json_format() {
+        let manifest = Manifest::new().file("hello.txt", b"hello");
+
+        assert_eq!(
+            manifest.to_json(),
+            "{\"files\":[{\"name\":\"hello.txt\",\"sha256\":\
+             \"2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824\",\"size\":5}]}"
+        );
+
+        let response = manifest.into_json().into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"manifest.json\""
+        );
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    }
+
+    #[test]
+    fn served_as_attachment() {
+        let response = Manifest::new().into_response();
+
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"SHA256SUMS\""
+        );
+    }
+}
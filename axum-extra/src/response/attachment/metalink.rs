@@ -0,0 +1,165 @@
+use super::Attachment;
+use axum::response::{IntoResponse, Response};
+use std::fmt::Write;
+
+/// A [Metalink] (`.meta4`) document describing a downloadable file.
+///
+/// Metalink lists the size, digests and mirror URLs of a file, so download managers can pick a
+/// mirror, fall back to another one when a mirror fails, and verify the result. The document is
+/// served as an [`Attachment`] named after the file with a `.meta4` extension.
+///
+/// [Metalink]: https://www.rfc-editor.org/rfc/rfc5854
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_extra::response::attachment::Metalink;
+///
+/// async fn metalink() -> Metalink {
+///     Metalink::new("release.tar.gz")
+///         .size(14_471_447)
+///         .url("https://eu.mirror.example.com/release.tar.gz", Some(1))
+///         .url("https://us.mirror.example.com/release.tar.gz", Some(2))
+/// }
+///
+/// let app = Router::new().route("/release.tar.gz.meta4", get(metalink));
+/// # let _: Router = app;
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct Metalink {
+    name: String,
+    size: Option<u64>,
+    sha256: Option<[u8; 32]>,
+    urls: Vec<(String, Option<u32>)>,
+}
+
+impl Metalink {
+    /// Create a new `Metalink` for the file `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            size: None,
+            sha256: None,
+            urls: Vec::new(),
+        }
+    }
+
+    /// Sets the size of the file in bytes.
+    pub fn size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    /// Sets the SHA-256 digest of the file.
+    pub fn sha256(mut self, sha256: [u8; 32]) -> Self {
+        self.sha256 = Some(sha256);
+        self
+    }
+
+    /// Adds a URL the file can be downloaded from.
+    ///
+    /// Mirrors with a lower `priority` are preferred, starting at 1.
+    pub fn url(mut self, url: impl Into<String>, priority: Option<u32>) -> Self {
+        self.urls.push((url.into(), priority));
+        self
+    }
+
+    /// Render the document as XML.
+    pub fn to_xml(&self) -> String {
+        let mut out = String::from(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <metalink xmlns=\"urn:ietf:params:xml:ns:metalink\">\n",
+        );
+        let _ = writeln!(out, "  <file name=\"{}\">", escape(&self.name));
+        if let Some(size) = self.size {
+            let _ = writeln!(out, "    <size>{size}</size>");
+        }
+        if let Some(sha256) = self.sha256 {
+            out.push_str("    <hash type=\"sha-256\">");
+            for byte in sha256 {
+                let _ = write!(out, "{byte:02x}");
+            }
+            out.push_str("</hash>\n");
+        }
+        for (url, priority) in &self.urls {
+            out.push_str("    <url");
+            if let Some(priority) = priority {
+                let _ = write!(out, " priority=\"{priority}\"");
+            }
+            let _ = writeln!(out, ">{}</url>", escape(url));
+        }
+        out.push_str("  </file>\n</metalink>\n");
+        out
+    }
+}
+
+impl IntoResponse for Metalink {
+    fn into_response(self) -> Response {
+        Attachment::new(self.to_xml())
+            .filename(format!("{}.meta4", self.name))
+            .content_type("application/metalink4+xml")
+            .into_response()
+    }
+}
+
+fn escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http::header;
+
+    #[test]
+    fn metalink_format() {
+        let metalink = Metalink::new("a&b.tar")
+            .size(3)
+            .sha256([0xab; 32])
+            .url("https://example.com/a&b.tar", Some(1))
+            .url("https://mirror.example.com/a&b.tar", None);
+
+        assert_eq!(
+            metalink.to_xml(),
+            format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+                 <metalink xmlns=\"urn:ietf:params:xml:ns:metalink\">\n  \
+                 <file name=\"a&amp;b.tar\">\n    \
+                 <size>3</size>\n    \
+                 <hash type=\"sha-256\">{}</hash>\n    \
+                 <url priority=\"1\">https://example.com/a&amp;b.tar</url>\n    \
+                 <url>https://mirror.example.com/a&amp;b.tar</url>\n  \
+                 </file>\n\
+                 </metalink>\n",
+                "ab".repeat(32)
+            )
+        );
+    }
+
+    #[test]
+    fn served_as_attachment() {
+        let response = Metalink::new("release.tar.gz").into_response();
+
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"release.tar.gz.meta4\""
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/metalink4+xml"
+        );
+    }
+}
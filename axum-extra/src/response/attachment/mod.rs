@@ -0,0 +1,1636 @@
+//! File attachment responses and helpers for serving downloads.
+
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use futures_util::TryStream;
+use http::{header, HeaderName, HeaderValue};
+use std::{borrow::Cow, collections::HashMap, path::Path, sync::Arc, time::SystemTime};
+use tracing::error;
+
+#[cfg(any(
+    feature = "attachment-json",
+    feature = "attachment-toml",
+    feature = "attachment-yaml"
+))]
+use serde::Serialize;
+#[cfg(feature = "attachment-deadline")]
+use std::time::Duration;
+
+use self::{filename::SharedFilenamePolicy, trace::SharedErrorReporter};
+
+const DEFAULT_MAX_FILENAME_LEN: usize = 255;
+const DEFAULT_MAX_HEADER_LEN: usize = 8 * 1024;
+
+mod arc;
+#[cfg(feature = "attachment-config")]
+mod config;
+#[cfg(feature = "attachment-deadline")]
+mod deadline;
+#[cfg(feature = "attachment-config")]
+mod defaults;
+mod error;
+mod etag;
+mod filename;
+mod image;
+mod inline;
+mod layer;
+#[cfg(feature = "attachment-digest")]
+mod manifest;
+mod metalink;
+mod path;
+mod range;
+mod redirect;
+mod related;
+#[cfg(feature = "attachment-serve-file")]
+mod serve_file;
+mod sniff;
+mod static_attachment;
+mod trace;
+
+pub use self::arc::ArcAttachment;
+#[cfg(feature = "attachment-config")]
+pub use self::config::AttachmentConfig;
+#[cfg(feature = "attachment-deadline")]
+pub use self::deadline::DeadlineExceeded;
+#[cfg(feature = "attachment-config")]
+pub use self::defaults::{AttachmentDefaults, AttachmentDefaultsLayer};
+pub use self::error::AttachmentError;
+pub use self::etag::etag_from_metadata;
+#[cfg(feature = "attachment-digest")]
+pub use self::etag::{etag_from_bytes, etag_from_reader};
+pub(crate) use self::filename::parse_filename;
+pub use self::filename::{
+    decode_ext_value, encode_ext_value, DispositionOverflow, ExistingDisposition,
+    ExtensionMismatch, FilenamePolicy, SanitizeFilename,
+};
+pub use self::inline::ForceInline;
+pub use self::layer::{AttachmentLayer, AttachmentService, ResponseFuture};
+#[cfg(feature = "attachment-digest")]
+pub use self::manifest::{Manifest, ManifestEntry};
+pub use self::metalink::Metalink;
+pub use self::path::resolve_download_path;
+pub use self::range::{
+    coalesce_ranges, parse_range_header, parse_range_header_with_limit, unsatisfied_content_range,
+    RangeError, RangeSpec,
+};
+pub use self::redirect::{presign_content_disposition, DownloadRedirect};
+pub use self::related::MultipartRelated;
+#[cfg(feature = "attachment-serve-file")]
+pub use self::serve_file::{DefaultFallback, ServeFileAsAttachment};
+pub use self::sniff::{ContentSniffing, SpoofedContent};
+pub use self::static_attachment::StaticAttachment;
+pub use self::trace::{ErrorReporter, FailedDownload};
+
+/// A file attachment response.
+///
+/// This type will set the `Content-Disposition` header to `attachment`. In response a webbrowser
+/// will offer to download the file instead of displaying it directly.
+///
+/// Use the `filename` and `content_type` methods to set the filename or content-type of the
+/// attachment. If these values are not set they will not be sent.
+///
+///
+/// # Example
+///
+/// ```rust
+///  use axum::{http::StatusCode, routing::get, Router};
+///  use axum_extra::response::Attachment;
+///
+///  async fn cargo_toml() -> Result<Attachment<String>, (StatusCode, String)> {
+///      let file_contents = tokio::fs::read_to_string("Cargo.toml")
+///          .await
+///          .map_err(|err| (StatusCode::NOT_FOUND, format!("File not found: {err}")))?;
+///      Ok(Attachment::new(file_contents)
+///          .filename("Cargo.toml")
+///          .content_type("text/x-toml"))
+///  }
+///
+///  let app = Router::new().route("/Cargo.toml", get(cargo_toml));
+///  let _: Router = app;
+/// ```
+///
+/// # Note
+///
+/// If you use axum with hyper, hyper will set the `Content-Length` if it is known.
+///
+/// An [`AttachmentInfo`] describing the download is inserted into the response extensions, so
+/// middleware can inspect it without parsing the headers again.
+///
+/// `Attachment` doesn't serve byte ranges. Unless the inner response sets `Accept-Ranges` itself,
+/// `Accept-Ranges: none` is sent so download managers don't retry with range requests.
+///
+#[derive(Debug)]
+pub struct Attachment<T> {
+    inner: T,
+    filename: Option<HeaderValue>,
+    content_type: Option<HeaderValue>,
+    filename_policy: Option<SharedFilenamePolicy>,
+    max_filename_len: Option<usize>,
+    max_header_len: usize,
+    disposition_overflow: DispositionOverflow,
+    cache_control: Option<HeaderValue>,
+    nosniff: bool,
+    expose_content_disposition: bool,
+    size_header: Option<HeaderName>,
+    generated_at: Option<(HeaderName, SystemTime)>,
+    extensions: &'static [&'static str],
+    replace_extension: bool,
+    #[cfg(feature = "attachment-deadline")]
+    deadline: Option<Duration>,
+    download_span: bool,
+    error_reporter: Option<SharedErrorReporter>,
+    default_extensions: Option<Arc<HashMap<String, String>>>,
+    extension_mismatch: Option<ExtensionMismatch>,
+    include_bodyless_responses: bool,
+    existing_disposition: ExistingDisposition,
+    content_sniffing: Option<ContentSniffing>,
+    harden_active_content: bool,
+}
+
+impl<T> Attachment<T> {
+    /// Creates a new [`Attachment`].
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            filename: None,
+            content_type: None,
+            filename_policy: None,
+            max_filename_len: None,
+            max_header_len: DEFAULT_MAX_HEADER_LEN,
+            disposition_overflow: DispositionOverflow::default(),
+            cache_control: None,
+            nosniff: false,
+            expose_content_disposition: false,
+            size_header: None,
+            generated_at: None,
+            extensions: &[],
+            replace_extension: false,
+            #[cfg(feature = "attachment-deadline")]
+            deadline: None,
+            download_span: false,
+            error_reporter: None,
+            default_extensions: None,
+            extension_mismatch: None,
+            include_bodyless_responses: false,
+            existing_disposition: ExistingDisposition::default(),
+            content_sniffing: None,
+            harden_active_content: false,
+        }
+    }
+
+    /// Sets the filename of the [`Attachment`].
+    ///
+    /// This updates the `Content-Disposition` header to add a filename. The filename is normalized
+    /// to Unicode Normalization Form C, so decomposed names, as produced by macOS, are sent the
+    /// same way as their precomposed equivalents. Filenames that aren't
+    /// plain ASCII are additionally sent in the `filename*` parameter, encoded as described in
+    /// [RFC 8187], with an ASCII approximation in the `filename` parameter for older clients.
+    ///
+    /// [RFC 8187]: https://www.rfc-editor.org/rfc/rfc8187
+    pub fn filename<H: TryInto<HeaderValue>>(mut self, value: H) -> Self {
+        self.filename = if let Ok(filename) = value.try_into() {
+            Some(filename)
+        } else {
+            error!("Attachment filename contains invalid characters");
+            None
+        };
+        self
+    }
+
+    /// Sets the filename of the [`Attachment`] to the final component of `path`.
+    ///
+    /// Unlike `path.file_name().unwrap().to_str().unwrap()`, this never panics. Parts of the
+    /// name that aren't valid UTF-8 are replaced with `U+FFFD`, characters that aren't safe in
+    /// filenames are replaced as by [`SanitizeFilename`], and if `path` doesn't end in a file
+    /// name, such as `/` or `exports/..`, no filename is sent.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum_extra::response::Attachment;
+    /// use std::path::Path;
+    ///
+    /// let path = Path::new("/var/exports/2024/report.csv");
+    /// // sent as `report.csv`
+    /// let attachment = Attachment::new("...").filename_from_path(path);
+    /// # let _ = attachment;
+    /// ```
+    pub fn filename_from_path(mut self, path: impl AsRef<Path>) -> Self {
+        match filename::from_path(path.as_ref()) {
+            Some(filename) => self.filename(filename),
+            None => {
+                self.filename = None;
+                self
+            }
+        }
+    }
+
+    /// Sets the content-type of the [`Attachment`]
+    pub fn content_type<H: TryInto<HeaderValue>>(mut self, value: H) -> Self {
+        if let Ok(content_type) = value.try_into() {
+            self.content_type = Some(content_type);
+        } else {
+            error!("Attachment content-type contains invalid characters");
+        }
+        self
+    }
+
+    /// Sets the [`FilenamePolicy`] applied to the filename of the [`Attachment`].
+    ///
+    /// The policy runs when the response is built, so it doesn't matter whether this is called
+    /// before or after [`filename`](Self::filename).
+    pub fn filename_policy<P: FilenamePolicy>(mut self, policy: P) -> Self {
+        self.filename_policy = Some(SharedFilenamePolicy(Arc::new(policy)));
+        self
+    }
+
+    /// Sets the maximum length of the filename in bytes.
+    ///
+    /// Longer filenames are shortened by truncating the stem, keeping the extension intact.
+    /// Defaults to 255 bytes, the filename limit of most file systems.
+    pub fn max_filename_len(mut self, len: usize) -> Self {
+        self.max_filename_len = Some(len);
+        self
+    }
+
+    /// Sets the maximum size of the `Content-Disposition` header line in bytes.
+    ///
+    /// Proxies commonly reject responses with header lines larger than 8 KiB, which is also the
+    /// default. If the header would be larger, `overflow` decides how it is shortened. If the
+    /// header still doesn't fit, the filename is dropped.
+    pub fn max_header_len(mut self, len: usize, overflow: DispositionOverflow) -> Self {
+        self.max_header_len = len;
+        self.disposition_overflow = overflow;
+        self
+    }
+
+    /// Sets the `Cache-Control` header of the [`Attachment`].
+    pub fn cache_control<H: TryInto<HeaderValue>>(mut self, value: H) -> Self {
+        if let Ok(cache_control) = value.try_into() {
+            self.cache_control = Some(cache_control);
+        } else {
+            error!("Attachment cache-control contains invalid characters");
+        }
+        self
+    }
+
+    /// Sends `X-Content-Type-Options: nosniff`, preventing browsers from guessing a different
+    /// content-type than the one that was sent.
+    pub fn nosniff(mut self) -> Self {
+        self.nosniff = true;
+        self
+    }
+
+    /// Hardens HTML and SVG attachments, which can run script if a client renders them.
+    ///
+    /// If the content type is `text/html`, `application/xhtml+xml` or `image/svg+xml`, this sends
+    /// `Content-Security-Policy: sandbox` and `X-Content-Type-Options: nosniff`, and always uses
+    /// the `attachment` disposition of the attachment, even if the inner response has its own
+    /// `Content-Disposition` and [`ExistingDisposition::Keep`] is used. Attachments of other types
+    /// are unchanged. Use this when serving user content that may contain HTML or SVG.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::response::IntoResponse;
+    /// use axum_extra::response::Attachment;
+    ///
+    /// let response = Attachment::new("<script>alert(1)</script>")
+    ///     .filename("upload.html")
+    ///     .content_type("text/html")
+    ///     .harden_active_content()
+    ///     .into_response();
+    /// assert_eq!(response.headers()["content-security-policy"], "sandbox");
+    /// assert_eq!(response.headers()["x-content-type-options"], "nosniff");
+    /// ```
+    pub fn harden_active_content(mut self) -> Self {
+        self.harden_active_content = true;
+        self
+    }
+
+    /// Lists `Content-Disposition` in `Access-Control-Expose-Headers`, so the filename can be read
+    /// from JavaScript in cross-origin requests.
+    pub fn expose_content_disposition(mut self) -> Self {
+        self.expose_content_disposition = true;
+        self
+    }
+
+    /// Sends the size of the body in bytes in the header `name`, such as `X-Download-Size`.
+    ///
+    /// Some proxies strip or rewrite `Content-Length`, which prevents clients from showing
+    /// progress. The header is only sent if the size is known up front.
+    pub fn size_header(mut self, name: HeaderName) -> Self {
+        self.size_header = Some(name);
+        self
+    }
+
+    /// Sends `time`, formatted as an HTTP date, in the header `name`.
+    ///
+    /// Use this to tell clients when a generated download, such as a report, was created.
+    pub fn generated_at_header(mut self, name: HeaderName, time: SystemTime) -> Self {
+        self.generated_at = Some((name, time));
+        self
+    }
+
+    /// Also add the attachment headers if the inner response is a redirect (3xx), or is
+    /// `204 No Content` or `205 Reset Content`.
+    ///
+    /// By default, these responses are sent unchanged, since there is nothing to download and some
+    /// browsers misbehave when they carry `Content-Disposition: attachment`.
+    pub fn include_bodyless_responses(mut self) -> Self {
+        self.include_bodyless_responses = true;
+        self
+    }
+
+    /// Sets what to do if the inner response already has a `Content-Disposition` header.
+    ///
+    /// This happens when wrapping a response proxied from another server. Defaults to
+    /// [`ExistingDisposition::Override`]. If the header is kept, the filename in the
+    /// [`AttachmentInfo`] is taken from it.
+    pub fn existing_disposition(mut self, existing: ExistingDisposition) -> Self {
+        self.existing_disposition = existing;
+        self
+    }
+
+    /// Limits the total time sending the body may take, starting when it is first polled.
+    ///
+    /// Unlike an idle timeout, this also stops clients that keep a download open by reading it
+    /// very slowly. Once the deadline has passed, the body fails with [`DeadlineExceeded`], which
+    /// makes hyper abort the response, and a warning is logged.
+    #[cfg(feature = "attachment-deadline")]
+    pub fn deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Reports the download in a `download` [`tracing`] span, which is entered whenever the body
+    /// is polled.
+    ///
+    /// The span has these fields, so dashboards can use the same queries across services:
+    ///
+    /// - `download.filename`: the filename sent to the client, if any.
+    /// - `download.bytes_total`: the size of the body, if known up front.
+    /// - `download.bytes_sent`: the number of bytes sent, recorded once the download ends.
+    /// - `download.status`: `completed`, `failed` if the body returned an error, or `aborted` if
+    ///   the body was dropped before it ended, usually because the client went away.
+    ///
+    /// [`tracing`]: https://docs.rs/tracing
+    pub fn download_span(mut self) -> Self {
+        self.download_span = true;
+        self
+    }
+
+    /// Sets an [`ErrorReporter`] that is called if the body fails while it is being sent.
+    ///
+    /// Clients cancelling the download aren't reported.
+    pub fn error_reporter<R: ErrorReporter>(mut self, reporter: R) -> Self {
+        self.error_reporter = Some(SharedErrorReporter(Arc::new(reporter)));
+        self
+    }
+
+    /// Sets what to do when the extension of the filename doesn't match the content type, such
+    /// as `report.csv` sent as `application/pdf`.
+    ///
+    /// This catches bugs in endpoints that would otherwise send files that can't be opened. Only
+    /// common extensions and content types are compared, others are always allowed. Defaults to
+    /// [`ExtensionMismatch::Allow`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::response::IntoResponse;
+    /// use axum_extra::response::{attachment::ExtensionMismatch, Attachment};
+    /// use http::StatusCode;
+    ///
+    /// let response = Attachment::new("%PDF-1.7 ...")
+    ///     .filename("report.csv")
+    ///     .content_type("application/pdf")
+    ///     .extension_mismatch(ExtensionMismatch::Reject)
+    ///     .into_response();
+    /// assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    /// ```
+    pub fn extension_mismatch(mut self, mismatch: ExtensionMismatch) -> Self {
+        self.extension_mismatch = Some(mismatch);
+        self
+    }
+
+    /// Sets what to do when the start of the body contradicts the content type.
+    ///
+    /// The first chunk of the body is compared with the content type, to catch HTML or SVG sent
+    /// as another type, such as `image/png`, and images sent as a different image type. Clients
+    /// that ignore the declared type can run script in HTML and SVG, so serving user uploads that
+    /// were stored with a spoofed type can lead to cross-site scripting. Bodies sent as
+    /// `application/octet-stream` aren't checked.
+    ///
+    /// Since the headers are sent before the body, [`ContentSniffing::Block`] fails the body
+    /// before any data is sent, which aborts the response. Defaults to [`ContentSniffing::Off`].
+    pub fn sniff_content(mut self, sniffing: ContentSniffing) -> Self {
+        self.content_sniffing = Some(sniffing);
+        self
+    }
+
+    /// Applies the settings from an [`AttachmentConfig`].
+    ///
+    /// Settings in the config take precedence over settings made earlier. To only fill in settings
+    /// that weren't made explicitly, use [`AttachmentDefaultsLayer`].
+    #[cfg(feature = "attachment-config")]
+    pub fn with_config(mut self, config: &AttachmentConfig) -> Self {
+        if let Some(cache_control) = &config.cache_control {
+            self = self.cache_control(cache_control.as_str());
+        }
+        if config.sanitize_filenames {
+            self = self.filename_policy(SanitizeFilename);
+        }
+        self.nosniff |= config.nosniff;
+        self.expose_content_disposition |= config.expose_content_disposition;
+        self.harden_active_content |= config.harden_active_content;
+        self.max_filename_len = Some(config.max_filename_len);
+        self.default_extensions = Some(Arc::new(config.default_extensions.clone()));
+        self.extension_mismatch = Some(config.extension_mismatch);
+        self.content_sniffing = Some(config.content_sniffing);
+        self
+    }
+
+    #[cfg(feature = "attachment-config")]
+    fn with_defaults(mut self, config: &AttachmentConfig) -> Self {
+        if self.cache_control.is_none() {
+            if let Some(cache_control) = &config.cache_control {
+                self = self.cache_control(cache_control.as_str());
+            }
+        }
+        if self.filename_policy.is_none() && config.sanitize_filenames {
+            self = self.filename_policy(SanitizeFilename);
+        }
+        self.nosniff |= config.nosniff;
+        self.expose_content_disposition |= config.expose_content_disposition;
+        self.harden_active_content |= config.harden_active_content;
+        self.max_filename_len = self.max_filename_len.or(Some(config.max_filename_len));
+        if self.default_extensions.is_none() {
+            self.default_extensions = Some(Arc::new(config.default_extensions.clone()));
+        }
+        self.extension_mismatch = self.extension_mismatch.or(Some(config.extension_mismatch));
+        self.content_sniffing = self.content_sniffing.or(Some(config.content_sniffing));
+        self
+    }
+}
+
+impl Attachment<String> {
+    /// Creates a plain text [`Attachment`] named `download.txt`.
+    pub fn text(text: impl Into<String>) -> Self {
+        Self::new(text.into())
+            .filename("download.txt")
+            .content_type(mime::TEXT_PLAIN_UTF_8.as_ref())
+    }
+
+    /// Creates an iCalendar [`Attachment`] named `calendar.ics`.
+    ///
+    /// The content type is `text/calendar; charset=utf-8; method=PUBLISH`. If the filename is
+    /// changed, `.ics` is appended unless it already ends in `.ics`. iCalendar requires CRLF line
+    /// endings, use [`crlf_line_endings`](Self::crlf_line_endings) if `calendar` might not use
+    /// them.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::{routing::get, Router};
+    /// use axum_extra::response::Attachment;
+    ///
+    /// async fn calendar() -> Attachment<String> {
+    ///     let calendar = "BEGIN:VCALENDAR\nVERSION:2.0\nPRODID:-//example//EN\nEND:VCALENDAR\n";
+    ///     Attachment::ics(calendar).filename("holidays").crlf_line_endings()
+    /// }
+    ///
+    /// let app = Router::new().route("/holidays.ics", get(calendar));
+    /// # let _: Router = app;
+    /// ```
+    pub fn ics(calendar: impl Into<String>) -> Self {
+        let mut attachment = Self::new(calendar.into())
+            .filename("calendar.ics")
+            .content_type("text/calendar; charset=utf-8; method=PUBLISH");
+        attachment.extensions = &["ics"];
+        attachment
+    }
+
+    /// Creates a vCard [`Attachment`] named `contacts.vcf`.
+    ///
+    /// The content type is `text/vcard; charset=utf-8`. If the filename is changed, `.vcf` is
+    /// appended unless it already ends in `.vcf` or `.vcard`. vCard requires CRLF line endings,
+    /// use [`crlf_line_endings`](Self::crlf_line_endings) if `contacts` might not use them.
+    pub fn vcard(contacts: impl Into<String>) -> Self {
+        let mut attachment = Self::new(contacts.into())
+            .filename("contacts.vcf")
+            .content_type("text/vcard; charset=utf-8");
+        attachment.extensions = &["vcf", "vcard"];
+        attachment
+    }
+
+    /// Converts all line endings of the body to CRLF (`\r\n`).
+    pub fn crlf_line_endings(mut self) -> Self {
+        if let Some(normalized) = crlf(&self.inner) {
+            self.inner = normalized;
+        }
+        self
+    }
+
+    /// Creates a CSV [`Attachment`] named `download.csv` from an already formatted string.
+    pub fn csv_string(csv: impl Into<String>) -> Self {
+        Self::new(csv.into())
+            .filename("download.csv")
+            .content_type("text/csv; charset=utf-8")
+    }
+}
+
+/// Convert lone `\n` and `\r` in `text` to `\r\n`, returning `None` if there are none.
+fn crlf(text: &str) -> Option<String> {
+    let bytes = text.as_bytes();
+    let needs_conversion = bytes.iter().enumerate().any(|(idx, &b)| match b {
+        b'\n' => idx == 0 || bytes[idx - 1] != b'\r',
+        b'\r' => bytes.get(idx + 1) != Some(&b'\n'),
+        _ => false,
+    });
+    if !needs_conversion {
+        return None;
+    }
+
+    let mut out = String::with_capacity(text.len() + text.len() / 16);
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                out.push_str("\r\n");
+            }
+            '\n' => out.push_str("\r\n"),
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+impl Attachment<Body> {
+    /// Creates an SQL dump [`Attachment`] from a stream of statements.
+    ///
+    /// The filename is `name` followed by the current UTC time and `.sql`, for example
+    /// `shop-20240102T030405Z.sql`, and the content type is `application/sql`. The output of a
+    /// dump tool such as `pg_dump` can be streamed by wrapping its stdout in a
+    /// [`ReaderStream`](https://docs.rs/tokio-util/latest/tokio_util/io/struct.ReaderStream.html).
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::{body::Body, routing::get, Router};
+    /// use axum_extra::response::Attachment;
+    /// use futures_util::stream;
+    /// use std::convert::Infallible;
+    ///
+    /// async fn dump() -> Attachment<Body> {
+    ///     let statements = stream::iter([
+    ///         Ok::<_, Infallible>("CREATE TABLE users (name TEXT);\n"),
+    ///         Ok("INSERT INTO users VALUES ('ferris');\n"),
+    ///     ]);
+    ///     Attachment::sql_dump("shop", statements)
+    /// }
+    ///
+    /// let app = Router::new().route("/dump", get(dump));
+    /// # let _: Router = app;
+    /// ```
+    pub fn sql_dump<S>(name: &str, statements: S) -> Self
+    where
+        S: TryStream + Send + 'static,
+        S::Ok: Into<Bytes>,
+        S::Error: Into<BoxError>,
+    {
+        Self::new(Body::from_stream(statements))
+            .filename(timestamped_filename(name, "sql", SystemTime::now()))
+            .content_type("application/sql")
+    }
+}
+
+/// Format `{name}-{YYYYMMDD}T{HHMMSS}Z.{extension}` for `time` in UTC.
+fn timestamped_filename(name: &str, extension: &str, time: SystemTime) -> String {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+    let (days, secs_of_day) = (secs / 86400, secs % 86400);
+
+    // civil date from days since the epoch, see http://howardhinnant.github.io/date_algorithms.html
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{name}-{year:04}{month:02}{day:02}T{:02}{:02}{:02}Z.{extension}",
+        secs_of_day / 3600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+    )
+}
+
+impl Attachment<Vec<u8>> {
+    /// Creates a compact JSON [`Attachment`] named `download.json` by serializing `value`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::{http::StatusCode, routing::get, Router};
+    /// use axum_extra::response::Attachment;
+    /// use serde::Serialize;
+    ///
+    /// #[derive(Serialize)]
+    /// struct Export {
+    ///     users: Vec<String>,
+    /// }
+    ///
+    /// async fn export() -> Result<Attachment<Vec<u8>>, StatusCode> {
+    ///     let export = Export { users: vec!["ferris".to_owned()] };
+    ///     let attachment = Attachment::json(&export).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    ///     Ok(attachment.filename("export.json"))
+    /// }
+    ///
+    /// let app = Router::new().route("/export", get(export));
+    /// # let _: Router = app;
+    /// ```
+    #[cfg(feature = "attachment-json")]
+    pub fn json<S: Serialize + ?Sized>(value: &S) -> Result<Self, serde_json::Error> {
+        Ok(Self::json_bytes(serde_json::to_vec(value)?))
+    }
+
+    /// Creates a pretty-printed JSON [`Attachment`] named `download.json` by serializing `value`.
+    #[cfg(feature = "attachment-json")]
+    pub fn json_pretty<S: Serialize + ?Sized>(value: &S) -> Result<Self, serde_json::Error> {
+        Ok(Self::json_bytes(serde_json::to_vec_pretty(value)?))
+    }
+
+    #[cfg(feature = "attachment-json")]
+    fn json_bytes(bytes: Vec<u8>) -> Self {
+        Self::new(bytes)
+            .filename("download.json")
+            .content_type(mime::APPLICATION_JSON.as_ref())
+    }
+}
+
+impl Attachment<String> {
+    /// Creates a YAML [`Attachment`] named `download.yaml` by serializing `value`.
+    ///
+    /// If the filename is changed, `.yaml` is appended unless it already ends in `.yaml` or
+    /// `.yml`.
+    #[cfg(feature = "attachment-yaml")]
+    pub fn yaml<S: Serialize + ?Sized>(value: &S) -> Result<Self, serde_yaml::Error> {
+        let mut attachment = Self::new(serde_yaml::to_string(value)?)
+            .filename("download.yaml")
+            .content_type("application/yaml");
+        attachment.extensions = &["yaml", "yml"];
+        Ok(attachment)
+    }
+
+    /// Creates a TOML [`Attachment`] named `download.toml` by serializing `value`.
+    ///
+    /// If the filename is changed, `.toml` is appended unless it already ends in `.toml`.
+    #[cfg(feature = "attachment-toml")]
+    pub fn toml<S: Serialize + ?Sized>(value: &S) -> Result<Self, toml::ser::Error> {
+        let mut attachment = Self::new(toml::to_string(value)?)
+            .filename("download.toml")
+            .content_type("application/toml");
+        attachment.extensions = &["toml"];
+        Ok(attachment)
+    }
+}
+
+impl<T> Attachment<T> {
+    /// Converts the [`Attachment`] into an [`http::Response`] without going through
+    /// [`IntoResponse`].
+    ///
+    /// This allows using `Attachment` from plain hyper or tower services, with any
+    /// [`http_body::Body`] as the inner value.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum_extra::response::Attachment;
+    /// use http_body_util::Full;
+    ///
+    /// let response = Attachment::new(Full::from("Hello!"))
+    ///     .filename("hello.txt")
+    ///     .into_http_response();
+    /// assert_eq!(response.headers()["content-disposition"], "attachment; filename=\"hello.txt\"");
+    /// ```
+    pub fn into_http_response(
+        self,
+    ) -> http::Response<impl HttpBody<Data = Bytes, Error = axum::Error> + Send + 'static>
+    where
+        T: HttpBody<Data = Bytes> + Send + 'static,
+        T::Error: Into<BoxError>,
+    {
+        match self.into_parts() {
+            Ok((inner, finish)) => finish.apply(http::Response::new(Body::new(inner))),
+            Err(response) => *response,
+        }
+    }
+
+    /// Split the attachment into the inner response and the parts applied to the response built
+    /// from it, or returns an error response.
+    fn into_parts(mut self) -> Result<(T, Finish), Box<Response>> {
+        #[cfg(feature = "attachment-config")]
+        if let Some(defaults) = defaults::current() {
+            self = self.with_defaults(&defaults);
+        }
+
+        let filename = self.filename.take().and_then(|filename| {
+            let mut filename = String::from_utf8_lossy(filename.as_bytes());
+            filename = if_changed(filename, filename::normalize);
+            if let Some(policy) = &self.filename_policy {
+                filename = if_changed(filename, |filename| policy.0.apply(filename));
+            }
+            if let Some(filename_with_extension) =
+                filename::ensure_extension(&filename, self.extensions, self.replace_extension)
+            {
+                filename = Cow::Owned(filename_with_extension);
+            }
+            if let (Some(extensions), Some(content_type)) =
+                (&self.default_extensions, &self.content_type)
+            {
+                if !filename.is_empty() && !filename::has_extension(&filename) {
+                    if let Some(extension) =
+                        filename::extension_for_content_type(extensions, content_type)
+                    {
+                        filename = Cow::Owned(format!("{filename}.{extension}"));
+                    }
+                }
+            }
+            let max_filename_len = self.max_filename_len.unwrap_or(DEFAULT_MAX_FILENAME_LEN);
+            filename = if_changed(filename, |filename| {
+                filename::truncate(filename, max_filename_len)
+            });
+            (!filename.is_empty()).then(|| filename.into_owned())
+        });
+
+        if let (Some(filename), Some(content_type)) = (&filename, &self.content_type) {
+            if let Some(expected) = filename::mismatched_extension(filename, content_type) {
+                match self.extension_mismatch.unwrap_or_default() {
+                    ExtensionMismatch::Allow => {}
+                    ExtensionMismatch::Warn => tracing::warn!(
+                        filename,
+                        ?content_type,
+                        expected,
+                        "attachment filename extension doesn't match its content type",
+                    ),
+                    ExtensionMismatch::Reject => {
+                        error!(
+                            filename,
+                            ?content_type,
+                            expected,
+                            "attachment filename extension doesn't match its content type",
+                        );
+                        return Err(Box::new(
+                            http::StatusCode::INTERNAL_SERVER_ERROR.into_response(),
+                        ));
+                    }
+                }
+            }
+        }
+
+        let content_disposition = if let Some(filename) = &filename {
+            filename::content_disposition_within(
+                "attachment",
+                filename,
+                self.max_header_len,
+                self.disposition_overflow,
+            )
+        } else {
+            HeaderValue::from_static("attachment")
+        };
+
+        let finish = Finish {
+            content_disposition,
+            content_type: self.content_type,
+            cache_control: self.cache_control,
+            nosniff: self.nosniff,
+            expose_content_disposition: self.expose_content_disposition,
+            filename,
+            size_header: self.size_header,
+            generated_at: self.generated_at,
+            #[cfg(feature = "attachment-deadline")]
+            deadline: self.deadline,
+            download_span: self.download_span,
+            error_reporter: self.error_reporter,
+            existing_disposition: self.existing_disposition,
+            content_sniffing: self.content_sniffing.unwrap_or_default(),
+            harden_active_content: self.harden_active_content,
+        };
+        Ok((self.inner, finish))
+    }
+}
+
+/// Replace `value` with the result of `f`, only if `f` changed it.
+fn if_changed<'a>(value: Cow<'a, str>, f: impl FnOnce(&str) -> Cow<'_, str>) -> Cow<'a, str> {
+    let changed = match f(&value) {
+        Cow::Borrowed(_) => None,
+        Cow::Owned(changed) => Some(changed),
+    };
+    changed.map_or(value, Cow::Owned)
+}
+
+/// The parts of an [`Attachment`] that are applied after the inner response is built.
+struct Finish {
+    content_disposition: HeaderValue,
+    content_type: Option<HeaderValue>,
+    cache_control: Option<HeaderValue>,
+    nosniff: bool,
+    expose_content_disposition: bool,
+    filename: Option<String>,
+    size_header: Option<HeaderName>,
+    generated_at: Option<(HeaderName, SystemTime)>,
+    #[cfg(feature = "attachment-deadline")]
+    deadline: Option<Duration>,
+    download_span: bool,
+    error_reporter: Option<SharedErrorReporter>,
+    existing_disposition: ExistingDisposition,
+    content_sniffing: ContentSniffing,
+    harden_active_content: bool,
+}
+
+impl Finish {
+    /// Drops the settings that wrap the body, for responses that are built ahead of time.
+    fn without_body_wrappers(self) -> Self {
+        Self {
+            #[cfg(feature = "attachment-deadline")]
+            deadline: None,
+            download_span: false,
+            error_reporter: None,
+            content_sniffing: ContentSniffing::Off,
+            ..self
+        }
+    }
+
+    fn apply(mut self, mut response: Response) -> Response {
+        let headers = response.headers_mut();
+        let harden = self.harden_active_content
+            && self
+                .content_type
+                .as_ref()
+                .or_else(|| headers.get(header::CONTENT_TYPE))
+                .map_or(false, is_active_content);
+        if harden {
+            self.nosniff = true;
+            headers.insert(
+                header::CONTENT_SECURITY_POLICY,
+                HeaderValue::from_static("sandbox"),
+            );
+        }
+
+        if let Some(existing) = headers.get(header::CONTENT_DISPOSITION) {
+            match self.existing_disposition {
+                ExistingDisposition::Override => {}
+                ExistingDisposition::Keep if harden => {}
+                ExistingDisposition::Keep => {
+                    self.filename = filename::parse_filename(existing);
+                    self.content_disposition = existing.clone();
+                }
+                ExistingDisposition::Error => {
+                    error!(
+                        ?existing,
+                        "response wrapped in an attachment already has a content-disposition",
+                    );
+                    return http::StatusCode::INTERNAL_SERVER_ERROR.into_response();
+                }
+            }
+        }
+        if let Some(content_type) = self.content_type {
+            headers.insert(header::CONTENT_TYPE, content_type);
+        }
+        if let Some(cache_control) = self.cache_control {
+            headers.insert(header::CACHE_CONTROL, cache_control);
+        }
+        if self.nosniff {
+            headers.insert(
+                header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            );
+        }
+        if self.expose_content_disposition {
+            headers.insert(
+                header::ACCESS_CONTROL_EXPOSE_HEADERS,
+                HeaderValue::from_static("content-disposition"),
+            );
+        }
+        headers.insert(header::CONTENT_DISPOSITION, self.content_disposition);
+
+        let size = response.body().size_hint().exact();
+
+        if let (Some(name), Some(size)) = (self.size_header, size) {
+            response.headers_mut().insert(name, HeaderValue::from(size));
+        }
+
+        if let Some((name, time)) = self.generated_at {
+            let date = HeaderValue::try_from(httpdate::fmt_http_date(time))
+                .expect("HTTP dates are valid header values");
+            response.headers_mut().insert(name, date);
+        }
+
+        // `Attachment` always sends the full body, so tell download managers not to bother with
+        // range requests unless the inner response serves ranges itself
+        if !response.headers().contains_key(header::ACCEPT_RANGES) {
+            response
+                .headers_mut()
+                .insert(header::ACCEPT_RANGES, HeaderValue::from_static("none"));
+        }
+
+        if self.content_sniffing != ContentSniffing::Off {
+            if let Some(content_type) = response.headers().get(header::CONTENT_TYPE).cloned() {
+                let sniffing = self.content_sniffing;
+                response = response
+                    .map(|body| Body::new(sniff::SniffBody::new(body, content_type, sniffing)));
+            }
+        }
+
+        #[cfg(feature = "attachment-deadline")]
+        if let Some(deadline) = self.deadline {
+            response = response.map(|body| Body::new(deadline::DeadlineBody::new(body, deadline)));
+        }
+
+        if self.download_span || self.error_reporter.is_some() {
+            let filename = self.filename.as_deref();
+            let (span, reporter) = (self.download_span, self.error_reporter);
+            response = response
+                .map(|body| Body::new(trace::TracedBody::new(body, filename, span, reporter)));
+        }
+
+        let info = AttachmentInfo {
+            filename: self.filename,
+            content_type: response.headers().get(header::CONTENT_TYPE).cloned(),
+            size,
+        };
+        response.extensions_mut().insert(info);
+
+        response
+    }
+}
+
+impl<T> IntoResponse for Attachment<T>
+where
+    T: IntoResponse,
+{
+    fn into_response(self) -> Response {
+        let include_bodyless_responses = self.include_bodyless_responses;
+        let (inner, finish) = match self.into_parts() {
+            Ok(parts) => parts,
+            Err(response) => return *response,
+        };
+
+        let response = inner.into_response();
+        if !include_bodyless_responses && is_bodyless(response.status()) {
+            return response;
+        }
+        finish.apply(response)
+    }
+}
+
+/// Whether `content_type` can run script if a client renders it.
+fn is_active_content(content_type: &HeaderValue) -> bool {
+    let Ok(content_type) = content_type.to_str() else {
+        return false;
+    };
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    ["text/html", "application/xhtml+xml", "image/svg+xml"]
+        .iter()
+        .any(|active| essence.eq_ignore_ascii_case(active))
+}
+
+/// Whether responses with `status` have nothing to download.
+fn is_bodyless(status: http::StatusCode) -> bool {
+    status.is_redirection()
+        || status == http::StatusCode::NO_CONTENT
+        || status == http::StatusCode::RESET_CONTENT
+}
+
+#[cfg(feature = "multipart")]
+impl From<crate::extract::multipart::Field> for Attachment<Body> {
+    /// Turn an uploaded multipart field back into an [`Attachment`], keeping its filename and
+    /// content-type.
+    ///
+    /// The field is streamed, not buffered.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::{body::Body, http::StatusCode, routing::post, Router};
+    /// use axum_extra::{extract::Multipart, response::Attachment};
+    ///
+    /// async fn echo(mut multipart: Multipart) -> Result<Attachment<Body>, StatusCode> {
+    ///     let field = multipart
+    ///         .next_field()
+    ///         .await
+    ///         .map_err(|_| StatusCode::BAD_REQUEST)?
+    ///         .ok_or(StatusCode::BAD_REQUEST)?;
+    ///     Ok(Attachment::from(field))
+    /// }
+    ///
+    /// let app = Router::new().route("/echo", post(echo));
+    /// # let _: Router = app;
+    /// ```
+    fn from(field: crate::extract::multipart::Field) -> Self {
+        let filename = field.file_name().map(str::to_owned);
+        let content_type = field.content_type().map(str::to_owned);
+
+        let mut attachment = Attachment::new(Body::from_stream(field));
+        if let Some(filename) = filename {
+            attachment = attachment.filename(filename);
+        }
+        if let Some(content_type) = content_type {
+            attachment = attachment.content_type(content_type);
+        }
+        attachment
+    }
+}
+
+/// Metadata about an [`Attachment`] response.
+///
+/// This is inserted into the response extensions by [`Attachment`], allowing middleware further
+/// down the stack to record what was downloaded.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{extract::Request, middleware::{self, Next}, response::Response, routing::get, Router};
+/// use axum_extra::response::{Attachment, AttachmentInfo};
+///
+/// async fn log_downloads(request: Request, next: Next) -> Response {
+///     let response = next.run(request).await;
+///     if let Some(info) = response.extensions().get::<AttachmentInfo>() {
+///         println!("downloaded {:?} ({:?} bytes)", info.filename(), info.size());
+///     }
+///     response
+/// }
+///
+/// let app = Router::new()
+///     .route("/", get(|| async { Attachment::new("Hello!").filename("hello.txt") }))
+///     .layer(middleware::from_fn(log_downloads));
+/// # let _: Router = app;
+/// ```
+#[derive(Debug, Clone)]
+pub struct AttachmentInfo {
+    filename: Option<String>,
+    content_type: Option<HeaderValue>,
+    size: Option<u64>,
+}
+
+impl AttachmentInfo {
+    /// The filename of the attachment, if one was set.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    /// The `Content-Type` of the response, if any.
+    pub fn content_type(&self) -> Option<&HeaderValue> {
+        self.content_type.as_ref()
+    }
+
+    /// The size of the body in bytes, if it is known up front.
+    pub fn size(&self) -> Option<u64> {
+        self.size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::BodyExt;
+
+    #[test]
+    fn inserts_attachment_info() {
+        let response = Attachment::new("hello")
+            .filename("hello.txt")
+            .content_type("text/plain")
+            .into_response();
+
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.filename().unwrap(), "hello.txt");
+        assert_eq!(info.content_type().unwrap(), "text/plain");
+        assert_eq!(info.size(), Some(5));
+    }
+
+    #[test]
+    fn attachment_info_without_filename() {
+        let response = Attachment::new(axum::body::Body::empty()).into_response();
+
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert!(info.filename().is_none());
+        assert!(info.content_type().is_none());
+        assert_eq!(info.size(), Some(0));
+    }
+
+    #[test]
+    fn advertises_no_range_support() {
+        let response = Attachment::new("hello").into_response();
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "none");
+
+        let inner = ([(header::ACCEPT_RANGES, "bytes")], "hello");
+        let response = Attachment::new(inner).into_response();
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+    }
+
+    #[test]
+    fn applies_filename_policy() {
+        let response = Attachment::new("hello")
+            .filename_policy(SanitizeFilename)
+            .filename("../secret.txt")
+            .into_response();
+
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\".._secret.txt\""
+        );
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.filename().unwrap(), ".._secret.txt");
+    }
+
+    #[test]
+    fn empty_filename_from_policy_omits_filename() {
+        let response = Attachment::new("hello")
+            .filename("report.csv")
+            .filename_policy(|_: &str| String::new())
+            .into_response();
+
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment"
+        );
+    }
+
+    #[cfg(feature = "attachment-config")]
+    #[test]
+    fn applies_config() {
+        let config = AttachmentConfig {
+            cache_control: Some("no-store".to_owned()),
+            nosniff: true,
+            sanitize_filenames: true,
+            ..Default::default()
+        };
+
+        let response = Attachment::new("hello")
+            .filename("a/b.txt")
+            .with_config(&config)
+            .into_response();
+
+        assert_eq!(response.headers()[header::CACHE_CONTROL], "no-store");
+        assert_eq!(
+            response.headers()[header::X_CONTENT_TYPE_OPTIONS],
+            "nosniff"
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"a_b.txt\""
+        );
+    }
+
+    #[cfg(feature = "attachment-config")]
+    #[test]
+    fn config_appends_default_extension() {
+        let config = AttachmentConfig::with_common_extensions();
+        let filename = |filename: &str, content_type: &str| {
+            let response = Attachment::new("hello")
+                .filename(filename)
+                .content_type(content_type)
+                .with_config(&config)
+                .into_response();
+            let info = response.extensions().get::<AttachmentInfo>().unwrap();
+            info.filename().unwrap().to_owned()
+        };
+
+        assert_eq!(filename("report", "text/csv"), "report.csv");
+        assert_eq!(filename("report", "text/csv; charset=utf-8"), "report.csv");
+        assert_eq!(filename("report.txt", "text/csv"), "report.txt");
+        assert_eq!(filename("report", "application/x-unknown"), "report");
+
+        // the default config keeps filenames as is
+        let response = Attachment::new("hello")
+            .filename("report")
+            .content_type("text/csv")
+            .with_config(&AttachmentConfig::default())
+            .into_response();
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.filename(), Some("report"));
+    }
+
+    #[test]
+    fn skips_bodyless_responses() {
+        let redirect = || axum::response::Redirect::to("/elsewhere");
+
+        let response = Attachment::new(redirect())
+            .filename("report.csv")
+            .content_type("text/csv")
+            .into_response();
+        assert_eq!(response.status(), http::StatusCode::SEE_OTHER);
+        assert!(response
+            .headers()
+            .get(header::CONTENT_DISPOSITION)
+            .is_none());
+        assert!(response.headers().get(header::CONTENT_TYPE).is_none());
+
+        let response = Attachment::new(http::StatusCode::NO_CONTENT).into_response();
+        assert!(response
+            .headers()
+            .get(header::CONTENT_DISPOSITION)
+            .is_none());
+
+        let response = Attachment::new(redirect())
+            .filename("report.csv")
+            .include_bodyless_responses()
+            .into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"report.csv\""
+        );
+    }
+
+    #[test]
+    fn existing_disposition() {
+        let upstream = || {
+            (
+                [(
+                    header::CONTENT_DISPOSITION,
+                    "attachment; filename=\"upstream.csv\"",
+                )],
+                "hello",
+            )
+        };
+
+        let response = Attachment::new(upstream())
+            .filename("report.csv")
+            .into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"report.csv\""
+        );
+        assert_eq!(
+            response
+                .headers()
+                .get_all(header::CONTENT_DISPOSITION)
+                .iter()
+                .count(),
+            1
+        );
+
+        let response = Attachment::new(upstream())
+            .filename("report.csv")
+            .existing_disposition(ExistingDisposition::Keep)
+            .into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"upstream.csv\""
+        );
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.filename(), Some("upstream.csv"));
+
+        let response = Attachment::new(upstream())
+            .existing_disposition(ExistingDisposition::Error)
+            .into_response();
+        assert_eq!(response.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(response
+            .headers()
+            .get(header::CONTENT_DISPOSITION)
+            .is_none());
+    }
+
+    #[test]
+    fn filename_from_path() {
+        let response = Attachment::new("hello")
+            .filename_from_path("exports/2024/na\u{303}o.csv")
+            .into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"n_o.csv\"; filename*=UTF-8''n%C3%A3o.csv"
+        );
+
+        let response = Attachment::new("hello")
+            .filename("report.csv")
+            .filename_from_path("exports/..")
+            .into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment"
+        );
+    }
+
+    #[test]
+    fn hardens_active_content() {
+        let response = Attachment::new((
+            [(header::CONTENT_DISPOSITION, "inline")],
+            "<svg><script>alert(1)</script></svg>",
+        ))
+        .filename("avatar.svg")
+        .content_type("image/svg+xml")
+        .existing_disposition(ExistingDisposition::Keep)
+        .harden_active_content()
+        .into_response();
+
+        assert_eq!(
+            response.headers()[header::CONTENT_SECURITY_POLICY],
+            "sandbox"
+        );
+        assert_eq!(
+            response.headers()[header::X_CONTENT_TYPE_OPTIONS],
+            "nosniff"
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"avatar.svg\""
+        );
+
+        let response = Attachment::new("a,b")
+            .content_type("text/csv")
+            .harden_active_content()
+            .into_response();
+        assert!(response
+            .headers()
+            .get(header::CONTENT_SECURITY_POLICY)
+            .is_none());
+        assert!(response
+            .headers()
+            .get(header::X_CONTENT_TYPE_OPTIONS)
+            .is_none());
+    }
+
+    #[test]
+    fn rejects_mismatched_extension() {
+        let attachment = || {
+            Attachment::new("hello")
+                .filename("report.csv")
+                .content_type("application/pdf")
+        };
+
+        let response = attachment().into_response();
+        assert_eq!(response.status(), http::StatusCode::OK);
+
+        let response = attachment()
+            .extension_mismatch(ExtensionMismatch::Warn)
+            .into_response();
+        assert_eq!(response.status(), http::StatusCode::OK);
+
+        let response = attachment()
+            .extension_mismatch(ExtensionMismatch::Reject)
+            .into_response();
+        assert_eq!(response.status(), http::StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(response
+            .headers()
+            .get(header::CONTENT_DISPOSITION)
+            .is_none());
+    }
+
+    #[cfg(feature = "multipart")]
+    #[crate::test]
+    async fn multipart_field_round_trip() {
+        use crate::{extract::Multipart, test_helpers::*};
+        use axum::{body::Body, routing::post, Router};
+
+        async fn echo(mut multipart: Multipart) -> Attachment<Body> {
+            multipart.next_field().await.unwrap().unwrap().into()
+        }
+
+        let client = TestClient::new(Router::new().route("/", post(echo)));
+
+        let form = reqwest::multipart::Form::new().part(
+            "file",
+            reqwest::multipart::Part::bytes(&b"id,name\n"[..])
+                .file_name("report.csv")
+                .mime_str("text/csv")
+                .unwrap(),
+        );
+        let res = client.post("/").multipart(form).await;
+
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "text/csv");
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"report.csv\""
+        );
+        assert_eq!(res.text().await, "id,name\n");
+    }
+
+    #[test]
+    fn encodes_non_ascii_filename() {
+        let response = Attachment::new("hello")
+            .filename("naïve \"résumé\".pdf")
+            .into_response();
+
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"na_ve \\\"r_sum_\\\".pdf\"; \
+             filename*=UTF-8''na%C3%AFve%20%22r%C3%A9sum%C3%A9%22.pdf"
+        );
+    }
+
+    #[test]
+    fn truncates_long_filename() {
+        let response = Attachment::new("hello")
+            .filename(format!("{}.csv", "a".repeat(300)))
+            .into_response();
+
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.filename().unwrap(), format!("{}.csv", "a".repeat(251)));
+
+        let response = Attachment::new("hello")
+            .filename("résumé.pdf")
+            .max_filename_len(8)
+            .into_response();
+
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.filename().unwrap(), "rés.pdf");
+    }
+
+    #[test]
+    fn sends_size_and_generated_at_headers() {
+        let generated_at = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(784111777);
+        let response = Attachment::new("hello")
+            .size_header(HeaderName::from_static("x-download-size"))
+            .generated_at_header(HeaderName::from_static("x-generated-at"), generated_at)
+            .into_response();
+
+        assert_eq!(response.headers()["x-download-size"], "5");
+        assert_eq!(
+            response.headers()["x-generated-at"],
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+    }
+
+    #[test]
+    fn convenience_constructors() {
+        let response = Attachment::text("hello").into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"download.txt\""
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/plain; charset=utf-8"
+        );
+
+        let response = Attachment::csv_string("a,b\n").into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+    }
+
+    #[cfg(feature = "attachment-json")]
+    #[test]
+    fn json() {
+        let response = Attachment::json(&["a", "b"])
+            .unwrap()
+            .filename("list.json")
+            .into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"list.json\""
+        );
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/json");
+    }
+
+    #[cfg(feature = "attachment-json")]
+    #[tokio::test]
+    async fn json_pretty() {
+        let response = Attachment::json_pretty(&["a"]).unwrap().into_response();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "[\n  \"a\"\n]");
+    }
+
+    #[cfg(feature = "attachment-yaml")]
+    #[test]
+    fn yaml_enforces_extension() {
+        let response = Attachment::yaml(&["a"])
+            .unwrap()
+            .filename("config")
+            .into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"config.yaml\""
+        );
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/yaml");
+
+        let response = Attachment::yaml(&["a"])
+            .unwrap()
+            .filename("config.YML")
+            .into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"config.YML\""
+        );
+    }
+
+    #[cfg(feature = "attachment-toml")]
+    #[tokio::test]
+    async fn toml_enforces_extension() {
+        #[derive(Serialize)]
+        struct Config {
+            port: u16,
+        }
+
+        let response = Attachment::toml(&Config { port: 3000 })
+            .unwrap()
+            .filename("config.json")
+            .into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"config.json.toml\""
+        );
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/toml");
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "port = 3000\n");
+    }
+
+    #[test]
+    fn ics_enforces_extension() {
+        let response = Attachment::ics("BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n")
+            .filename("holidays")
+            .into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"holidays.ics\""
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/calendar; charset=utf-8; method=PUBLISH"
+        );
+    }
+
+    #[test]
+    fn vcard_enforces_extension() {
+        let response = Attachment::vcard("BEGIN:VCARD\nVERSION:4.0\nFN:Ferris\nEND:VCARD\n")
+            .filename("ferris")
+            .crlf_line_endings()
+            .into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"ferris.vcf\""
+        );
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/vcard; charset=utf-8"
+        );
+    }
+
+    #[tokio::test]
+    async fn sql_dump() {
+        let statements = futures_util::stream::iter([
+            Ok::<_, std::convert::Infallible>("CREATE TABLE t (a INT);\n"),
+            Ok("INSERT INTO t VALUES (1);\n"),
+        ]);
+        let response = Attachment::sql_dump("shop", statements).into_response();
+
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/sql");
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        let filename = info.filename().unwrap();
+        assert!(filename.starts_with("shop-"), "{filename}");
+        assert!(filename.ends_with("Z.sql"), "{filename}");
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "CREATE TABLE t (a INT);\nINSERT INTO t VALUES (1);\n");
+    }
+
+    #[test]
+    fn timestamped_filenames() {
+        let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(784111777);
+        assert_eq!(
+            timestamped_filename("db", "sql", time),
+            "db-19941106T084937Z.sql"
+        );
+
+        let time = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(951782400);
+        assert_eq!(
+            timestamped_filename("db", "sql", time),
+            "db-20000229T000000Z.sql"
+        );
+    }
+
+    #[test]
+    fn crlf_line_endings() {
+        assert_eq!(crlf("a\r\nb"), None);
+        assert_eq!(crlf("a\nb\rc\r\nd\n").unwrap(), "a\r\nb\r\nc\r\nd\r\n");
+        assert_eq!(crlf("\n").unwrap(), "\r\n");
+
+        let attachment = Attachment::ics("BEGIN:VCALENDAR\nEND:VCALENDAR\n").crlf_line_endings();
+        assert_eq!(attachment.inner, "BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n");
+    }
+
+    #[test]
+    fn limits_header_len() {
+        let response = Attachment::new("hello")
+            .filename("€.pdf")
+            .max_header_len(60, DispositionOverflow::DropExtendedFilename)
+            .into_response();
+
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"_.pdf\""
+        );
+    }
+}
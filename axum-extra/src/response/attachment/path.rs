@@ -0,0 +1,103 @@
+use super::AttachmentError;
+use std::path::{Path, PathBuf};
+
+/// The maximum length of a single path component, the limit of most file systems.
+const MAX_COMPONENT_LEN: usize = 255;
+
+/// Safely join a user-supplied, `/`-separated path onto `base_dir`.
+///
+/// The result is guaranteed to be inside `base_dir`, as long as `base_dir` contains no symlinks
+/// pointing elsewhere. Paths are rejected with [`AttachmentError::InvalidFilename`] if they:
+///
+/// - are empty,
+/// - are absolute, or start with a Windows drive or UNC prefix,
+/// - contain `..` components, backslashes, colons or NUL bytes,
+/// - contain a component longer than 255 bytes.
+///
+/// Empty and `.` components are skipped, so `a//./b` resolves to `base_dir/a/b`.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{extract::Path, routing::get, Router};
+/// use axum_extra::response::{
+///     attachment::{resolve_download_path, AttachmentError},
+///     Attachment,
+/// };
+///
+/// async fn download(Path(name): Path<String>) -> Result<Attachment<Vec<u8>>, AttachmentError> {
+///     let path = resolve_download_path("downloads", &name)?;
+///     let contents = tokio::fs::read(&path).await?;
+///     Ok(Attachment::new(contents).filename(name))
+/// }
+///
+/// let app = Router::new().route("/downloads/*name", get(download));
+/// # let _: Router = app;
+/// ```
+pub fn resolve_download_path(
+    base_dir: impl AsRef<Path>,
+    user_supplied: &str,
+) -> Result<PathBuf, AttachmentError> {
+    if user_supplied.contains(['\0', '\\']) || user_supplied.starts_with('/') {
+        return Err(AttachmentError::InvalidFilename);
+    }
+
+    let mut path = base_dir.as_ref().to_path_buf();
+    let mut pushed = false;
+    for component in user_supplied.split('/') {
+        match component {
+            "" | "." => {}
+            ".." => return Err(AttachmentError::InvalidFilename),
+            // drive prefixes such as `C:` would replace the base directory on Windows
+            c if c.contains(':') => return Err(AttachmentError::InvalidFilename),
+            c if c.len() > MAX_COMPONENT_LEN => return Err(AttachmentError::InvalidFilename),
+            c => {
+                path.push(c);
+                pushed = true;
+            }
+        }
+    }
+
+    if pushed {
+        Ok(path)
+    } else {
+        Err(AttachmentError::InvalidFilename)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resolve(path: &str) -> Option<PathBuf> {
+        resolve_download_path("base", path).ok()
+    }
+
+    #[test]
+    fn joins_relative_paths() {
+        assert_eq!(resolve("a.txt"), Some(Path::new("base").join("a.txt")));
+        assert_eq!(
+            resolve("dir//./a.txt"),
+            Some(Path::new("base").join("dir").join("a.txt"))
+        );
+    }
+
+    #[test]
+    fn rejects_unsafe_paths() {
+        for path in [
+            "",
+            ".",
+            "/etc/passwd",
+            "../secret",
+            "a/../../secret",
+            "a\\..\\secret",
+            "C:/Windows",
+            "a\0b",
+        ] {
+            assert_eq!(resolve(path), None, "{path:?}");
+        }
+
+        assert_eq!(resolve(&"a".repeat(256)), None);
+        assert!(resolve(&"a".repeat(255)).is_some());
+    }
+}
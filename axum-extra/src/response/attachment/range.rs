@@ -0,0 +1,330 @@
+use http::HeaderValue;
+use std::{fmt, ops::Range};
+
+/// A single satisfiable byte range, as parsed by [`parse_range_header`].
+///
+/// Both `start` and `end` are inclusive, matching the notation used by the `Range` and
+/// `Content-Range` headers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RangeSpec {
+    start: u64,
+    end: u64,
+}
+
+impl RangeSpec {
+    /// The position of the first byte in the range.
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// The position of the last byte in the range.
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// The range as a half-open [`Range`], suitable for slicing.
+    pub fn to_range(&self) -> Range<u64> {
+        self.start..self.end + 1
+    }
+
+    /// Format the `Content-Range` header for this range of a resource with `len` bytes.
+    pub fn content_range(&self, len: u64) -> HeaderValue {
+        let value = format!("bytes {}-{}/{}", self.start, self.end, len);
+        HeaderValue::try_from(value).expect("formatted integers are a valid header value")
+    }
+}
+
+/// Format the `Content-Range` header sent with a `416 Range Not Satisfiable` response for a
+/// resource with `len` bytes.
+pub fn unsatisfied_content_range(len: u64) -> HeaderValue {
+    HeaderValue::try_from(format!("bytes */{len}"))
+        .expect("formatted integers are a valid header value")
+}
+
+/// Parse a `Range` header against a resource of `len` bytes.
+///
+/// Follows [RFC 9110]: suffix ranges (`-500`) and open ranges (`500-`) are supported, ranges
+/// extending past the end of the resource are clamped, and ranges that start past the end are
+/// skipped. The returned ranges are in the order they appeared in the header.
+///
+/// # Errors
+///
+/// Returns [`RangeError::Malformed`] if the header is not a valid `bytes` range. Servers should
+/// ignore such a header and respond with the full resource.
+///
+/// Returns [`RangeError::Unsatisfiable`] if none of the ranges overlap the resource. Servers
+/// should respond with `416 Range Not Satisfiable` and [`unsatisfied_content_range`].
+///
+/// Returns [`RangeError::TooManyRanges`] if the header contains more than 32 ranges. Use
+/// [`parse_range_header_with_limit`] to configure the limit.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::http::HeaderValue;
+/// use axum_extra::response::attachment::parse_range_header;
+///
+/// let ranges = parse_range_header(&HeaderValue::from_static("bytes=0-99, -100"), 1000).unwrap();
+///
+/// assert_eq!(ranges[0].to_range(), 0..100);
+/// assert_eq!(ranges[1].to_range(), 900..1000);
+/// assert_eq!(ranges[1].content_range(1000), "bytes 900-999/1000");
+/// ```
+///
+/// [RFC 9110]: https://www.rfc-editor.org/rfc/rfc9110#name-range
+pub fn parse_range_header(header: &HeaderValue, len: u64) -> Result<Vec<RangeSpec>, RangeError> {
+    parse_range_header_with_limit(header, len, DEFAULT_MAX_RANGES)
+}
+
+const DEFAULT_MAX_RANGES: usize = 32;
+
+/// Parse a `Range` header like [`parse_range_header`], accepting at most `max_ranges` ranges.
+///
+/// Large numbers of ranges are expensive to serve and are a known denial of service vector, so
+/// requests exceeding the limit are rejected with [`RangeError::TooManyRanges`] before any range
+/// is processed. Servers should respond to that with `416 Range Not Satisfiable`.
+pub fn parse_range_header_with_limit(
+    header: &HeaderValue,
+    len: u64,
+    max_ranges: usize,
+) -> Result<Vec<RangeSpec>, RangeError> {
+    let header = header.to_str().map_err(|_| RangeError::Malformed)?;
+
+    let (unit, ranges) = header.split_once('=').ok_or(RangeError::Malformed)?;
+    if !unit.trim().eq_ignore_ascii_case("bytes") {
+        return Err(RangeError::Malformed);
+    }
+
+    let mut satisfiable = Vec::new();
+    let mut count = 0;
+
+    for range in ranges.split(',') {
+        let range = range.trim();
+        if range.is_empty() {
+            // empty list elements are allowed by the list syntax
+            continue;
+        }
+        count += 1;
+        if count > max_ranges {
+            return Err(RangeError::TooManyRanges);
+        }
+
+        let (first, last) = range.split_once('-').ok_or(RangeError::Malformed)?;
+        let (first, last) = (first.trim(), last.trim());
+
+        let spec = if first.is_empty() {
+            let suffix = parse_position(last)?;
+            if suffix == 0 || len == 0 {
+                continue;
+            }
+            RangeSpec {
+                start: len.saturating_sub(suffix),
+                end: len - 1,
+            }
+        } else {
+            let start = parse_position(first)?;
+            let end = if last.is_empty() {
+                None
+            } else {
+                Some(parse_position(last)?)
+            };
+
+            if end.map_or(false, |end| end < start) {
+                return Err(RangeError::Malformed);
+            }
+            if start >= len {
+                continue;
+            }
+            RangeSpec {
+                start,
+                end: end.map_or(len - 1, |end| end.min(len - 1)),
+            }
+        };
+
+        satisfiable.push(spec);
+    }
+
+    if count == 0 {
+        Err(RangeError::Malformed)
+    } else if satisfiable.is_empty() {
+        Err(RangeError::Unsatisfiable)
+    } else {
+        Ok(satisfiable)
+    }
+}
+
+/// Merge overlapping and adjacent ranges, returning them sorted by position.
+///
+/// [RFC 9110] recommends coalescing ranges before serving them, so that a client can't make the
+/// server send the same bytes many times over.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::http::HeaderValue;
+/// use axum_extra::response::attachment::{coalesce_ranges, parse_range_header};
+///
+/// let ranges = parse_range_header(&HeaderValue::from_static("bytes=50-99,0-49,10-19"), 1000)
+///     .unwrap();
+/// let ranges = coalesce_ranges(ranges);
+///
+/// assert_eq!(ranges.len(), 1);
+/// assert_eq!(ranges[0].to_range(), 0..100);
+/// ```
+///
+/// [RFC 9110]: https://www.rfc-editor.org/rfc/rfc9110#section-14.2
+pub fn coalesce_ranges(mut ranges: Vec<RangeSpec>) -> Vec<RangeSpec> {
+    ranges.sort_unstable_by_key(|range| range.start);
+
+    let mut coalesced: Vec<RangeSpec> = Vec::with_capacity(ranges.len());
+    for range in ranges {
+        match coalesced.last_mut() {
+            Some(last) if range.start <= last.end.saturating_add(1) => {
+                last.end = last.end.max(range.end);
+            }
+            _ => coalesced.push(range),
+        }
+    }
+    coalesced
+}
+
+fn parse_position(s: &str) -> Result<u64, RangeError> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(RangeError::Malformed);
+    }
+    s.parse().map_err(|_| RangeError::Malformed)
+}
+
+/// Error returned by [`parse_range_header`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RangeError {
+    /// The header is not a valid `bytes` range.
+    Malformed,
+    /// None of the requested ranges overlap the resource.
+    Unsatisfiable,
+    /// The header contains more ranges than allowed.
+    TooManyRanges,
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => f.write_str("Malformed `Range` header"),
+            Self::Unsatisfiable => f.write_str("Requested range not satisfiable"),
+            Self::TooManyRanges => f.write_str("Too many ranges requested"),
+        }
+    }
+}
+
+impl std::error::Error for RangeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(header: &'static str, len: u64) -> Result<Vec<(u64, u64)>, RangeError> {
+        parse_range_header(&HeaderValue::from_static(header), len)
+            .map(|ranges| ranges.iter().map(|r| (r.start(), r.end())).collect())
+    }
+
+    #[test]
+    fn single_ranges() {
+        assert_eq!(parse("bytes=0-499", 1000), Ok(vec![(0, 499)]));
+        assert_eq!(parse("bytes=500-999", 1000), Ok(vec![(500, 999)]));
+        assert_eq!(parse("bytes=500-", 1000), Ok(vec![(500, 999)]));
+        assert_eq!(parse("bytes=-500", 1000), Ok(vec![(500, 999)]));
+        assert_eq!(parse("bytes=0-0", 1000), Ok(vec![(0, 0)]));
+    }
+
+    #[test]
+    fn clamps_to_length() {
+        assert_eq!(parse("bytes=900-2000", 1000), Ok(vec![(900, 999)]));
+        assert_eq!(parse("bytes=-2000", 1000), Ok(vec![(0, 999)]));
+    }
+
+    #[test]
+    fn multiple_ranges() {
+        assert_eq!(
+            parse("bytes=0-9, 20-29 ,-5", 100),
+            Ok(vec![(0, 9), (20, 29), (95, 99)])
+        );
+        assert_eq!(parse("bytes=0-9,,20-29", 100), Ok(vec![(0, 9), (20, 29)]));
+    }
+
+    #[test]
+    fn unit_is_case_insensitive() {
+        assert_eq!(parse("Bytes=0-9", 100), Ok(vec![(0, 9)]));
+    }
+
+    #[test]
+    fn skips_unsatisfiable_ranges() {
+        assert_eq!(parse("bytes=0-9,200-299", 100), Ok(vec![(0, 9)]));
+        assert_eq!(parse("bytes=100-", 100), Err(RangeError::Unsatisfiable));
+        assert_eq!(parse("bytes=-0", 100), Err(RangeError::Unsatisfiable));
+        assert_eq!(parse("bytes=0-", 0), Err(RangeError::Unsatisfiable));
+        assert_eq!(parse("bytes=-10", 0), Err(RangeError::Unsatisfiable));
+    }
+
+    #[test]
+    fn malformed() {
+        for header in [
+            "",
+            "bytes",
+            "bytes=",
+            "bytes=,",
+            "items=0-9",
+            "bytes=9-0",
+            "bytes=-",
+            "bytes=a-b",
+            "bytes=+1-2",
+            "bytes=0-9;20-29",
+            "bytes=99999999999999999999-",
+        ] {
+            assert_eq!(parse(header, 100), Err(RangeError::Malformed), "{header}");
+        }
+    }
+
+    #[test]
+    fn limits_number_of_ranges() {
+        let header = HeaderValue::from_static("bytes=0-0,1-1,2-2");
+        assert_eq!(
+            parse_range_header_with_limit(&header, 100, 3)
+                .unwrap()
+                .len(),
+            3
+        );
+        assert_eq!(
+            parse_range_header_with_limit(&header, 100, 2),
+            Err(RangeError::TooManyRanges)
+        );
+
+        let flood = HeaderValue::try_from(format!("bytes={}", "0-,".repeat(1000))).unwrap();
+        assert_eq!(
+            parse_range_header(&flood, 100),
+            Err(RangeError::TooManyRanges)
+        );
+    }
+
+    #[test]
+    fn coalesces_overlapping_and_adjacent_ranges() {
+        let ranges = parse_range_header(
+            &HeaderValue::from_static("bytes=20-29,0-9,10-14,25-39,50-59,-5"),
+            100,
+        )
+        .unwrap();
+
+        let coalesced: Vec<_> = coalesce_ranges(ranges)
+            .iter()
+            .map(|r| (r.start(), r.end()))
+            .collect();
+        assert_eq!(coalesced, [(0, 14), (20, 39), (50, 59), (95, 99)]);
+    }
+
+    #[test]
+    fn content_range() {
+        let range = parse_range_header(&HeaderValue::from_static("bytes=10-19"), 100).unwrap();
+        assert_eq!(range[0].content_range(100), "bytes 10-19/100");
+        assert_eq!(unsatisfied_content_range(100), "bytes */100");
+    }
+}
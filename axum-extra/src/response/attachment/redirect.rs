@@ -0,0 +1,262 @@
+use super::{filename, DEFAULT_MAX_FILENAME_LEN};
+use axum::response::{IntoResponse, Response};
+use http::{header, HeaderValue, StatusCode};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/// Characters allowed unencoded in a query parameter value, the unreserved set of RFC 3986.
+const QUERY_VALUE: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Response that redirects a download to another location, such as a presigned storage URL.
+///
+/// The `Content-Disposition` header is set on the redirect itself. Since browsers use the headers
+/// of the final response, [`response_overrides`](Self::response_overrides) can additionally
+/// pass the disposition to storage services that support response-override query parameters,
+/// like S3 and Google Cloud Storage.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_extra::response::attachment::DownloadRedirect;
+///
+/// async fn download() -> DownloadRedirect {
+///     DownloadRedirect::temporary("https://bucket.s3.amazonaws.com/reports/2024.csv")
+///         .filename("report.csv")
+///         .content_type("text/csv")
+///         .response_overrides()
+/// }
+///
+/// let app = Router::new().route("/report", get(download));
+/// # let _: Router = app;
+/// ```
+#[must_use = "needs to be returned from a handler or otherwise turned into a Response to be useful"]
+#[derive(Debug, Clone)]
+pub struct DownloadRedirect {
+    status_code: StatusCode,
+    location: String,
+    filename: Option<String>,
+    content_type: Option<String>,
+    response_overrides: bool,
+}
+
+impl DownloadRedirect {
+    /// Create a new [`DownloadRedirect`] that uses a `302 Found` status code.
+    ///
+    /// # Panics
+    ///
+    /// If `uri` isn't a valid [`HeaderValue`].
+    pub fn found(uri: &str) -> Self {
+        Self::with_status_code(StatusCode::FOUND, uri)
+    }
+
+    /// Create a new [`DownloadRedirect`] that uses a `307 Temporary Redirect` status code.
+    ///
+    /// # Panics
+    ///
+    /// If `uri` isn't a valid [`HeaderValue`].
+    pub fn temporary(uri: &str) -> Self {
+        Self::with_status_code(StatusCode::TEMPORARY_REDIRECT, uri)
+    }
+
+    fn with_status_code(status_code: StatusCode, uri: &str) -> Self {
+        HeaderValue::try_from(uri).expect("URI isn't a valid header value");
+
+        Self {
+            status_code,
+            location: uri.to_owned(),
+            filename: None,
+            content_type: None,
+            response_overrides: false,
+        }
+    }
+
+    /// Sets the filename of the download.
+    ///
+    /// The filename is normalized and encoded the same way as by
+    /// [`Attachment::filename`](super::Attachment::filename).
+    pub fn filename(mut self, filename: impl Into<String>) -> Self {
+        self.filename = Some(filename.into());
+        self
+    }
+
+    /// Sets the content-type the storage service should send.
+    ///
+    /// This is only sent when [`response_overrides`](Self::response_overrides) is enabled, since
+    /// the redirect itself has no body.
+    pub fn content_type(mut self, content_type: impl Into<String>) -> Self {
+        self.content_type = Some(content_type.into());
+        self
+    }
+
+    /// Appends `response-content-disposition` and `response-content-type` query parameters to
+    /// the location.
+    ///
+    /// S3 and Google Cloud Storage send these values as the headers of the download. Presigned
+    /// URLs only accept these parameters if they were included when the URL was signed, so don't
+    /// use this for URLs that are already signed.
+    pub fn response_overrides(mut self) -> Self {
+        self.response_overrides = true;
+        self
+    }
+}
+
+impl IntoResponse for DownloadRedirect {
+    fn into_response(self) -> Response {
+        let content_disposition = attachment_disposition(self.filename.as_deref());
+
+        let mut location = self.location;
+        if self.response_overrides {
+            let mut params = vec![(
+                "response-content-disposition",
+                content_disposition
+                    .to_str()
+                    .expect("disposition is always ASCII"),
+            )];
+            if let Some(content_type) = &self.content_type {
+                params.push(("response-content-type", content_type));
+            }
+            location = append_query(&location, &params);
+        }
+
+        let location = match HeaderValue::try_from(location) {
+            Ok(location) => location,
+            Err(_) => {
+                tracing::error!("DownloadRedirect location contains invalid characters");
+                return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            }
+        };
+
+        (
+            self.status_code,
+            [
+                (header::LOCATION, location),
+                (header::CONTENT_DISPOSITION, content_disposition),
+            ],
+        )
+            .into_response()
+    }
+}
+
+/// Encode a filename as a `response-content-disposition` query parameter.
+///
+/// S3 and Google Cloud Storage send the value of this parameter as the `Content-Disposition`
+/// header of the download, so presigned URLs that include it show the right filename. The
+/// filename is normalized and encoded the same way as by
+/// [`Attachment::filename`](super::Attachment::filename), including a `filename*` parameter as
+/// described in [RFC 8187] for non-ASCII filenames.
+///
+/// The returned string is the complete, percent-encoded `name=value` pair, ready to be appended
+/// to the query string before the URL is signed.
+///
+/// # Example
+///
+/// ```rust
+/// use axum_extra::response::attachment::presign_content_disposition;
+///
+/// assert_eq!(
+///     presign_content_disposition("report.csv"),
+///     "response-content-disposition=attachment%3B%20filename%3D%22report.csv%22",
+/// );
+/// ```
+///
+/// [RFC 8187]: https://www.rfc-editor.org/rfc/rfc8187
+pub fn presign_content_disposition(filename: &str) -> String {
+    let content_disposition = attachment_disposition(Some(filename));
+    let content_disposition = content_disposition
+        .to_str()
+        .expect("disposition is always ASCII");
+
+    let mut out = String::from("response-content-disposition=");
+    out.extend(utf8_percent_encode(content_disposition, QUERY_VALUE));
+    out
+}
+
+fn attachment_disposition(filename: Option<&str>) -> HeaderValue {
+    let filename = filename.and_then(|filename| {
+        let filename = filename::normalize(filename);
+        let filename = filename::truncate(&filename, DEFAULT_MAX_FILENAME_LEN).into_owned();
+        (!filename.is_empty()).then_some(filename)
+    });
+
+    if let Some(filename) = &filename {
+        filename::content_disposition("attachment", filename)
+    } else {
+        HeaderValue::from_static("attachment")
+    }
+}
+
+/// Append query parameters to `uri`, keeping any fragment at the end.
+fn append_query(uri: &str, params: &[(&str, &str)]) -> String {
+    let (uri, fragment) = match uri.find('#') {
+        Some(idx) => uri.split_at(idx),
+        None => (uri, ""),
+    };
+
+    let mut out = String::from(uri);
+    let mut separator = if uri.contains('?') { '&' } else { '?' };
+    for (name, value) in params {
+        out.push(separator);
+        out.push_str(name);
+        out.push('=');
+        out.extend(utf8_percent_encode(value, QUERY_VALUE));
+        separator = '&';
+    }
+    out.push_str(fragment);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redirects_with_disposition() {
+        let res = DownloadRedirect::found("https://example.com/a")
+            .filename("report.csv")
+            .into_response();
+
+        assert_eq!(res.status(), StatusCode::FOUND);
+        assert_eq!(res.headers()[header::LOCATION], "https://example.com/a");
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"report.csv\""
+        );
+    }
+
+    #[test]
+    fn appends_response_overrides() {
+        let res = DownloadRedirect::temporary("https://example.com/a?X-Amz-Expires=60#top")
+            .filename("rapport €.csv")
+            .content_type("text/csv")
+            .response_overrides()
+            .into_response();
+
+        assert_eq!(res.status(), StatusCode::TEMPORARY_REDIRECT);
+        assert_eq!(
+            res.headers()[header::LOCATION],
+            "https://example.com/a?X-Amz-Expires=60\
+             &response-content-disposition=attachment%3B%20filename%3D%22rapport%20_.csv%22%3B%20filename%2A%3DUTF-8%27%27rapport%2520%25E2%2582%25AC.csv\
+             &response-content-type=text%2Fcsv#top"
+        );
+    }
+
+    #[test]
+    fn presign_non_ascii_filename() {
+        assert_eq!(
+            presign_content_disposition("€.pdf"),
+            "response-content-disposition=attachment%3B%20filename%3D%22_.pdf%22%3B%20filename%2A%3DUTF-8%27%27%25E2%2582%25AC.pdf"
+        );
+    }
+
+    #[test]
+    fn append_query_without_existing_query() {
+        assert_eq!(
+            append_query("/a", &[("x", "1 2"), ("y", "3")]),
+            "/a?x=1%202&y=3"
+        );
+    }
+}
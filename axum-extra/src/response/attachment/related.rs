@@ -0,0 +1,310 @@
+use super::Attachment;
+use axum::{
+    body::{Body, Bytes},
+    response::{IntoResponse, Response},
+};
+use futures_util::stream;
+use http::{header, HeaderMap, HeaderName, HeaderValue, StatusCode};
+use std::{
+    collections::hash_map::RandomState,
+    convert::Infallible,
+    fmt::Write,
+    hash::{BuildHasher, Hasher},
+};
+
+/// A [`multipart/related`] response, bundling a root part with the attachments it refers to.
+///
+/// Some batch download protocols, like those of several document APIs, send a root part, such as
+/// a JSON or XML document, followed by the files it refers to by `Content-ID`. The first part is
+/// the root. Attachments keep their `Content-Type` and `Content-Disposition`, so clients get the
+/// same filenames as when downloading them one by one.
+///
+/// [`multipart/related`]: https://www.rfc-editor.org/rfc/rfc2387
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_extra::response::{attachment::MultipartRelated, Attachment};
+///
+/// async fn batch() -> MultipartRelated {
+///     MultipartRelated::new()
+///         .part(
+///             "manifest",
+///             "application/json",
+///             r#"{"files":["cid:report","cid:logo"]}"#,
+///         )
+///         .attachment(
+///             "report",
+///             Attachment::new("id,name\n").filename("report.csv").content_type("text/csv"),
+///         )
+///         .attachment(
+///             "logo",
+///             Attachment::new(&b"\x89PNG\r\n\x1a\n"[..])
+///                 .filename("logo.png")
+///                 .content_type("image/png"),
+///         )
+/// }
+///
+/// let app = Router::new().route("/batch", get(batch));
+/// # let _: Router = app;
+/// ```
+#[derive(Debug, Default)]
+#[must_use]
+pub struct MultipartRelated {
+    parts: Vec<Part>,
+    failed: bool,
+}
+
+#[derive(Debug)]
+struct Part {
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl MultipartRelated {
+    /// Create an empty `MultipartRelated` response.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a part with the given `Content-ID` and content type.
+    ///
+    /// The angle brackets around `content_id` may be omitted. Other parts can refer to this part
+    /// with a `cid:` URL. If `content_type` isn't a valid [`HeaderValue`], an error is logged and
+    /// the part is sent without a `Content-Type`.
+    ///
+    /// # Panics
+    ///
+    /// If `content_id` isn't a valid [`HeaderValue`].
+    pub fn part<H: TryInto<HeaderValue>>(
+        mut self,
+        content_id: &str,
+        content_type: H,
+        body: impl Into<Bytes>,
+    ) -> Self {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ID, content_id_value(content_id));
+        if let Ok(content_type) = content_type.try_into() {
+            headers.insert(header::CONTENT_TYPE, content_type);
+        } else {
+            tracing::error!("MultipartRelated part content-type contains invalid characters");
+        }
+        self.parts.push(Part {
+            headers,
+            body: body.into(),
+        });
+        self
+    }
+
+    /// Adds an [`Attachment`] as a part with the given `Content-ID`.
+    ///
+    /// The part has the `Content-Type` and `Content-Disposition` of the attachment. Settings that
+    /// wrap the body, such as [`Attachment::deadline`], aren't applied. If the attachment would
+    /// respond with an error, for example because of
+    /// [`Attachment::extension_mismatch`], the whole response is `500 Internal Server Error`.
+    ///
+    /// # Panics
+    ///
+    /// If `content_id` isn't a valid [`HeaderValue`].
+    pub fn attachment<T: Into<Bytes>>(
+        mut self,
+        content_id: &str,
+        attachment: Attachment<T>,
+    ) -> Self {
+        let content_id = content_id_value(content_id);
+        let (inner, finish) = match attachment.into_parts() {
+            Ok(parts) => parts,
+            Err(_) => {
+                self.failed = true;
+                return self;
+            }
+        };
+        let finish = finish.without_body_wrappers();
+
+        let body = inner.into();
+        let response = finish.apply(Response::new(Body::from(body.clone())));
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_ID, content_id);
+        for name in [header::CONTENT_TYPE, header::CONTENT_DISPOSITION] {
+            if let Some(value) = response.headers().get(&name) {
+                headers.insert(name, value.clone());
+            }
+        }
+        self.parts.push(Part { headers, body });
+        self
+    }
+
+    fn boundary(&self) -> String {
+        loop {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_usize(self.parts.len());
+            let boundary = format!("axum-related-{:016x}", hasher.finish());
+            let delimiter = format!("--{boundary}");
+            if !self
+                .parts
+                .iter()
+                .any(|part| contains(&part.body, delimiter.as_bytes()))
+            {
+                return boundary;
+            }
+        }
+    }
+}
+
+const CONTENT_ID: HeaderName = HeaderName::from_static("content-id");
+
+fn content_id_value(content_id: &str) -> HeaderValue {
+    let content_id = if content_id.starts_with('<') && content_id.ends_with('>') {
+        content_id.to_owned()
+    } else {
+        format!("<{content_id}>")
+    };
+    HeaderValue::try_from(content_id).expect("invalid content id")
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+impl IntoResponse for MultipartRelated {
+    fn into_response(self) -> Response {
+        if self.failed {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+
+        let boundary = self.boundary();
+        let mut content_type = format!("multipart/related; boundary=\"{boundary}\"");
+        if let Some(root) = self.parts.first() {
+            if let Some(root_type) = root
+                .headers
+                .get(header::CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+            {
+                let root_type = root_type.split(';').next().unwrap_or_default().trim();
+                let _ = write!(content_type, "; type=\"{root_type}\"");
+            }
+            if let Some(start) = root
+                .headers
+                .get(CONTENT_ID)
+                .and_then(|value| value.to_str().ok())
+            {
+                let _ = write!(content_type, "; start=\"{start}\"");
+            }
+        }
+
+        let mut chunks = Vec::with_capacity(self.parts.len() * 3 + 1);
+        for part in self.parts {
+            let mut head = format!("--{boundary}\r\n").into_bytes();
+            for (name, value) in &part.headers {
+                head.extend_from_slice(name.as_str().as_bytes());
+                head.extend_from_slice(b": ");
+                head.extend_from_slice(value.as_bytes());
+                head.extend_from_slice(b"\r\n");
+            }
+            head.extend_from_slice(b"\r\n");
+            chunks.push(Bytes::from(head));
+            chunks.push(part.body);
+            chunks.push(Bytes::from_static(b"\r\n"));
+        }
+        chunks.push(Bytes::from(format!("--{boundary}--\r\n")));
+
+        let body = Body::from_stream(stream::iter(chunks.into_iter().map(Ok::<_, Infallible>)));
+        (
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::try_from(content_type).expect("boundary is a valid header value"),
+            )],
+            body,
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::attachment::ExtensionMismatch;
+    use http_body_util::BodyExt;
+
+    #[tokio::test]
+    async fn renders_parts() {
+        let response = MultipartRelated::new()
+            .part("root", "application/json; charset=utf-8", "{}")
+            .attachment(
+                "<report@example.com>",
+                Attachment::new("a,b\r\n")
+                    .filename("report.csv")
+                    .content_type("text/csv"),
+            )
+            .into_response();
+
+        let content_type = response.headers()[header::CONTENT_TYPE].to_str().unwrap();
+        let boundary = content_type
+            .split("boundary=\"")
+            .nth(1)
+            .unwrap()
+            .split('"')
+            .next()
+            .unwrap()
+            .to_owned();
+        assert_eq!(
+            content_type,
+            format!(
+                "multipart/related; boundary=\"{boundary}\"; \
+                 type=\"application/json\"; start=\"<root>\""
+            )
+        );
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(
+            body,
+            format!(
+                "--{boundary}\r\n\
+                 content-id: <root>\r\n\
+                 content-type: application/json; charset=utf-8\r\n\
+                 \r\n\
+                 {{}}\r\n\
+                 --{boundary}\r\n\
+                 content-id: <report@example.com>\r\n\
+                 content-type: text/csv\r\n\
+                 content-disposition: attachment; filename=\"report.csv\"\r\n\
+                 \r\n\
+                 a,b\r\n\r\n\
+                 --{boundary}--\r\n"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn invalid_part_content_type() {
+        let response = MultipartRelated::new()
+            .part("root", "text/plain\n", "hello")
+            .into_response();
+
+        let content_type = response.headers()[header::CONTENT_TYPE].to_str().unwrap();
+        assert!(!content_type.contains("type=\"text/plain"));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(!String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("content-type"));
+    }
+
+    #[test]
+    fn failed_attachment() {
+        let response = MultipartRelated::new()
+            .attachment(
+                "report",
+                Attachment::new("...")
+                    .filename("report.csv")
+                    .content_type("application/pdf")
+                    .extension_mismatch(ExtensionMismatch::Reject),
+            )
+            .into_response();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
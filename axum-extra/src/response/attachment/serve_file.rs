@@ -0,0 +1,347 @@
+use super::{Attachment, AttachmentError};
+use crate::body::SizedStream;
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    response::{IntoResponse, Response},
+    BoxError,
+};
+use futures_util::future::BoxFuture;
+use http::{header, HeaderValue, Method, Request, StatusCode};
+use std::{
+    convert::Infallible,
+    path::{Path, PathBuf},
+    task::{Context, Poll},
+};
+use tokio_util::io::ReaderStream;
+use tower::ServiceExt;
+use tower_service::Service;
+
+/// Service that serves a single file as an [`Attachment`].
+///
+/// The file is opened for every request and streamed with its length, so `Content-Length` is
+/// set. The filename defaults to the last component of the path, processed like
+/// [`Attachment::filename_from_path`], so names that aren't valid UTF-8 are served with
+/// replacement characters. Errors opening the file are turned into responses by
+/// [`AttachmentError`].
+///
+/// Since this is a [`Service`], it can be used as a route service or fallback, or be wrapped in
+/// other middleware, without a handler. Use [`or_else`](Self::or_else) to delegate to another
+/// service if the file can't be served. Requests other than `GET` and `HEAD` are rejected with
+/// `405 Method Not Allowed`.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::Router;
+/// use axum_extra::response::attachment::ServeFileAsAttachment;
+///
+/// let app = Router::new().route_service(
+///     "/terms",
+///     ServeFileAsAttachment::new("assets/terms-2024.pdf")
+///         .filename("terms.pdf")
+///         .content_type("application/pdf"),
+/// );
+/// # let _: Router = app;
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct ServeFileAsAttachment<F = DefaultFallback> {
+    path: PathBuf,
+    filename: Option<String>,
+    content_type: Option<HeaderValue>,
+    fallback: F,
+}
+
+/// The default fallback of [`ServeFileAsAttachment`], which responds with the
+/// [`AttachmentError`] itself.
+#[derive(Debug, Clone, Copy)]
+pub struct DefaultFallback(());
+
+impl ServeFileAsAttachment {
+    /// Create a new `ServeFileAsAttachment` serving the file at `path`.
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        let path = path.as_ref().to_path_buf();
+        let filename = super::filename::from_path(&path);
+
+        Self {
+            path,
+            filename,
+            content_type: None,
+            fallback: DefaultFallback(()),
+        }
+    }
+}
+
+impl<F> ServeFileAsAttachment<F> {
+    /// Delegate to `fallback` if the file doesn't exist or can't be accessed, instead of
+    /// responding with `404 Not Found` or `403 Forbidden`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use axum::{routing::get, Router};
+    /// use axum_extra::response::attachment::ServeFileAsAttachment;
+    ///
+    /// let app = Router::new().route_service(
+    ///     "/report.csv",
+    ///     ServeFileAsAttachment::new("reports/latest.csv")
+    ///         .or_else(get(|| async { "No report has been generated yet" })),
+    /// );
+    /// # let _: Router = app;
+    /// ```
+    pub fn or_else<F2>(self, fallback: F2) -> ServeFileAsAttachment<F2> {
+        ServeFileAsAttachment {
+            path: self.path,
+            filename: self.filename,
+            content_type: self.content_type,
+            fallback,
+        }
+    }
+
+    /// Sets the filename sent to the client, instead of the name of the file.
+    pub fn filename(mut self, filename: impl Into<String>) -> Self {
+        self.filename = Some(filename.into());
+        self
+    }
+
+    /// Sets the content-type of the file.
+    pub fn content_type<H: TryInto<HeaderValue>>(mut self, value: H) -> Self {
+        if let Ok(content_type) = value.try_into() {
+            self.content_type = Some(content_type);
+        } else {
+            tracing::error!("ServeFileAsAttachment content-type contains invalid characters");
+        }
+        self
+    }
+
+    async fn serve(
+        path: PathBuf,
+        filename: Option<String>,
+        content_type: Option<HeaderValue>,
+    ) -> Result<Response, AttachmentError> {
+        let file = tokio::fs::File::open(&path).await?;
+        let metadata = file.metadata().await?;
+        if !metadata.is_file() {
+            return Err(AttachmentError::NotFound);
+        }
+
+        let body = SizedStream::new(ReaderStream::new(file), metadata.len());
+        let mut attachment = Attachment::new(body);
+        if let Some(filename) = filename {
+            attachment = attachment.filename(filename);
+        }
+        if let Some(content_type) = content_type {
+            attachment = attachment.content_type(content_type);
+        }
+        Ok(attachment.into_response())
+    }
+}
+
+fn method_not_allowed(method: &Method) -> Option<Response> {
+    if method == Method::GET || method == Method::HEAD {
+        return None;
+    }
+    let allow = [(header::ALLOW, HeaderValue::from_static("GET, HEAD"))];
+    Some((StatusCode::METHOD_NOT_ALLOWED, allow).into_response())
+}
+
+impl<B> Service<Request<B>> for ServeFileAsAttachment<DefaultFallback> {
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        if let Some(response) = method_not_allowed(req.method()) {
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let path = self.path.clone();
+        let filename = self.filename.clone();
+        let content_type = self.content_type.clone();
+
+        Box::pin(async move {
+            Ok(Self::serve(path, filename, content_type)
+                .await
+                .unwrap_or_else(IntoResponse::into_response))
+        })
+    }
+}
+
+impl<B, F, ResBody> Service<Request<B>> for ServeFileAsAttachment<F>
+where
+    B: Send + 'static,
+    F: Service<Request<B>, Response = http::Response<ResBody>> + Clone + Send + 'static,
+    F::Future: Send,
+    ResBody: HttpBody<Data = Bytes> + Send + 'static,
+    ResBody::Error: Into<BoxError>,
+{
+    type Response = Response;
+    type Error = F::Error;
+    type Future = BoxFuture<'static, Result<Response, F::Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        if let Some(response) = method_not_allowed(req.method()) {
+            return Box::pin(async move { Ok(response) });
+        }
+
+        let path = self.path.clone();
+        let filename = self.filename.clone();
+        let content_type = self.content_type.clone();
+        let fallback = self.fallback.clone();
+
+        Box::pin(async move {
+            match Self::serve(path, filename, content_type).await {
+                Ok(response) => Ok(response),
+                Err(AttachmentError::NotFound | AttachmentError::PermissionDenied) => {
+                    let response = fallback.oneshot(req).await?;
+                    Ok(response.map(Body::new))
+                }
+                Err(err) => Ok(err.into_response()),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::*;
+    use axum::{routing::get, Router};
+    use http::{header, StatusCode};
+
+    #[crate::test]
+    async fn serves_file() {
+        let app = Router::new()
+            .route_service("/", ServeFileAsAttachment::new("Cargo.toml"))
+            .route_service(
+                "/renamed",
+                ServeFileAsAttachment::new("Cargo.toml")
+                    .filename("manifest.toml")
+                    .content_type("application/toml"),
+            );
+        let client = TestClient::new(app);
+
+        let res = client.get("/").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"Cargo.toml\""
+        );
+        let expected = std::fs::read_to_string("Cargo.toml").unwrap();
+        assert_eq!(
+            res.headers()[header::CONTENT_LENGTH],
+            expected.len().to_string()
+        );
+        assert_eq!(res.text().await, expected);
+
+        let res = client.get("/renamed").await;
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"manifest.toml\""
+        );
+        assert_eq!(res.headers()[header::CONTENT_TYPE], "application/toml");
+    }
+
+    #[crate::test]
+    async fn falls_back_to_inner_service() {
+        let app = Router::new()
+            .route_service(
+                "/",
+                ServeFileAsAttachment::new("does-not-exist.txt")
+                    .or_else(get(|| async { "fallback" })),
+            )
+            .route_service(
+                "/exists",
+                ServeFileAsAttachment::new("Cargo.toml").or_else(get(|| async { "fallback" })),
+            );
+        let client = TestClient::new(app);
+
+        let res = client.get("/").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(res.headers().get(header::CONTENT_DISPOSITION).is_none());
+        assert_eq!(res.text().await, "fallback");
+
+        let res = client.get("/exists").await;
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"Cargo.toml\""
+        );
+    }
+
+    #[cfg(unix)]
+    #[crate::test]
+    async fn non_utf8_filename() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = std::env::temp_dir().join(format!("axum-extra-non-utf8-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(OsStr::from_bytes(b"caf\xe9.txt"));
+        if std::fs::write(&path, "hello").is_err() {
+            // some file systems only allow UTF-8 names
+            return;
+        }
+
+        let client =
+            TestClient::new(Router::new().route_service("/", ServeFileAsAttachment::new(&path)));
+        let res = client.get("/").await;
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"caf_.txt\"; filename*=UTF-8''caf%EF%BF%BD.txt"
+        );
+        assert_eq!(res.text().await, "hello");
+    }
+
+    #[crate::test]
+    async fn only_get_and_head_are_allowed() {
+        let app = Router::new()
+            .route_service("/", ServeFileAsAttachment::new("Cargo.toml"))
+            .route_service(
+                "/fallback",
+                ServeFileAsAttachment::new("does-not-exist.txt")
+                    .or_else(get(|| async { "fallback" })),
+            );
+        let client = TestClient::new(app);
+
+        let res = client.head("/").await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"Cargo.toml\""
+        );
+
+        let res = client.post("/").await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "GET, HEAD");
+        assert!(res.headers().get(header::CONTENT_DISPOSITION).is_none());
+
+        let res = client.put("/fallback").await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(res.headers()[header::ALLOW], "GET, HEAD");
+    }
+
+    #[crate::test]
+    async fn missing_file() {
+        let app = Router::new()
+            .route_service("/", ServeFileAsAttachment::new("does-not-exist.txt"))
+            .route_service("/dir", ServeFileAsAttachment::new("src"));
+        let client = TestClient::new(app);
+
+        let res = client.get("/").await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let res = client.get("/dir").await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
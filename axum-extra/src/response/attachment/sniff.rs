@@ -0,0 +1,287 @@
+use super::image::ImageFormat;
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    BoxError, Error,
+};
+use futures_util::ready;
+use http::HeaderValue;
+use http_body::{Frame, SizeHint};
+use pin_project_lite::pin_project;
+#[cfg(feature = "attachment-config")]
+use serde::Deserialize;
+use std::{
+    fmt,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+/// What to do when the start of the body of an [`Attachment`] contradicts its content type.
+///
+/// See [`Attachment::sniff_content`] for details.
+///
+/// [`Attachment`]: super::Attachment
+/// [`Attachment::sniff_content`]: super::Attachment::sniff_content
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "attachment-config",
+    derive(Deserialize),
+    serde(rename_all = "snake_case")
+)]
+#[non_exhaustive]
+pub enum ContentSniffing {
+    /// Don't inspect the body.
+    #[default]
+    Off,
+    /// Log a warning and send the body anyway.
+    Warn,
+    /// Log an error and fail the body with [`SpoofedContent`] before any data is sent.
+    Block,
+}
+
+/// Error produced when the body of an attachment contradicts its content type and
+/// [`ContentSniffing::Block`] is used.
+///
+/// This is wrapped in an [`axum::Error`] and can be recovered with
+/// [`into_inner`](axum::Error::into_inner) and [`downcast`](std::error::Error#method.downcast).
+#[derive(Debug)]
+pub struct SpoofedContent {
+    declared: HeaderValue,
+    detected: &'static str,
+}
+
+impl SpoofedContent {
+    /// The content type the attachment was sent with.
+    pub fn declared(&self) -> &HeaderValue {
+        &self.declared
+    }
+
+    /// The content type detected from the body.
+    pub fn detected(&self) -> &'static str {
+        self.detected
+    }
+}
+
+impl fmt::Display for SpoofedContent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "attachment declared as {:?} looks like {}",
+            self.declared, self.detected
+        )
+    }
+}
+
+impl std::error::Error for SpoofedContent {}
+
+/// Returns the content type `data` appears to be, if it contradicts `declared`.
+///
+/// Only contradictions that are certain, or that could make a client run script, are reported:
+/// HTML or SVG sent as another type, and images sent as a different image type. XHTML and generic
+/// XML types are compatible with HTML and SVG respectively.
+pub(super) fn contradiction(declared: &HeaderValue, data: &[u8]) -> Option<&'static str> {
+    let declared = declared.to_str().ok()?;
+    let declared = declared.split(';').next().unwrap_or_default().trim();
+    let is = |content_type: &str| declared.eq_ignore_ascii_case(content_type);
+
+    if let Some(format) = ImageFormat::detect(data) {
+        let declared_image = declared
+            .get(..6)
+            .map_or(false, |prefix| prefix.eq_ignore_ascii_case("image/"));
+        return (declared_image && !is(format.content_type)).then_some(format.content_type);
+    }
+
+    let markup = detect_markup(data)?;
+    let compatible: &[&str] = match markup {
+        "text/html" => &["text/html", "application/xhtml+xml"],
+        _ => &["image/svg+xml", "application/xml", "text/xml"],
+    };
+    (!compatible.iter().any(|content_type| is(content_type)) && !is("application/octet-stream"))
+        .then_some(markup)
+}
+
+/// Detect HTML and SVG documents by their first tag.
+fn detect_markup(data: &[u8]) -> Option<&'static str> {
+    let start = data.iter().position(|b| !b.is_ascii_whitespace())?;
+    let data = data[start..]
+        .strip_prefix(b"\xef\xbb\xbf")
+        .unwrap_or(&data[start..]);
+    let starts_with = |tag: &[u8]| {
+        data.len() > tag.len()
+            && data[..tag.len()].eq_ignore_ascii_case(tag)
+            && matches!(data[tag.len()], b' ' | b'>' | b'\t' | b'\n' | b'\r' | b'/')
+    };
+
+    if starts_with(b"<svg") {
+        Some("image/svg+xml")
+    } else if [
+        &b"<!doctype html"[..],
+        b"<html",
+        b"<head",
+        b"<body",
+        b"<script",
+        b"<iframe",
+    ]
+    .iter()
+    .any(|tag| starts_with(tag))
+    {
+        Some("text/html")
+    } else if data.starts_with(b"<?xml") && contains(&data[..data.len().min(1024)], b"<svg") {
+        Some("image/svg+xml")
+    } else {
+        None
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window.eq_ignore_ascii_case(needle))
+}
+
+pin_project! {
+    /// Body that compares its first chunk with the declared content type.
+    pub(super) struct SniffBody {
+        #[pin]
+        inner: Body,
+        declared: Option<HeaderValue>,
+        mode: ContentSniffing,
+        failed: bool,
+    }
+}
+
+impl SniffBody {
+    pub(super) fn new(inner: Body, declared: HeaderValue, mode: ContentSniffing) -> Self {
+        Self {
+            inner,
+            declared: Some(declared),
+            mode,
+            failed: false,
+        }
+    }
+}
+
+impl HttpBody for SniffBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let this = self.project();
+
+        if *this.failed {
+            return Poll::Ready(None);
+        }
+
+        let frame = ready!(this.inner.poll_frame(cx));
+        if let Some(data) = frame.as_ref().and_then(|f| f.as_ref().ok()?.data_ref()) {
+            if !data.is_empty() {
+                if let Some(declared) = this.declared.take() {
+                    if let Some(detected) = contradiction(&declared, data) {
+                        if *this.mode == ContentSniffing::Block {
+                            tracing::error!(
+                                ?declared,
+                                detected,
+                                "attachment body contradicts its content type, blocking it",
+                            );
+                            *this.failed = true;
+                            return Poll::Ready(Some(Err(Box::new(SpoofedContent {
+                                declared,
+                                detected,
+                            }))));
+                        }
+                        tracing::warn!(
+                            ?declared,
+                            detected,
+                            "attachment body contradicts its content type",
+                        );
+                    }
+                }
+            }
+        }
+        Poll::Ready(frame.map(|frame| frame.map_err(Error::into_inner)))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.failed || self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::Attachment;
+    use axum::response::IntoResponse;
+    use http_body_util::BodyExt;
+
+    fn check(declared: &'static str, data: &[u8]) -> Option<&'static str> {
+        contradiction(&HeaderValue::from_static(declared), data)
+    }
+
+    #[test]
+    fn detects_contradictions() {
+        assert_eq!(
+            check("image/png", b"<html><script>alert(1)</script>"),
+            Some("text/html")
+        );
+        assert_eq!(
+            check("text/plain", b"\n  <!DOCTYPE html>\n<p>"),
+            Some("text/html")
+        );
+        assert_eq!(
+            check("image/png", b"<svg xmlns=\"http://www.w3.org/2000/svg\">"),
+            Some("image/svg+xml")
+        );
+        assert_eq!(
+            check("image/jpeg", b"<?xml version=\"1.0\"?>\n<svg>"),
+            Some("image/svg+xml")
+        );
+        assert_eq!(check("image/png", b"\xff\xd8\xff\xe0"), Some("image/jpeg"));
+
+        assert_eq!(check("text/html; charset=utf-8", b"<html>"), None);
+        assert_eq!(check("image/svg+xml", b"<svg>"), None);
+        assert_eq!(check("application/xhtml+xml", b"<html xmlns=\"\">"), None);
+        assert_eq!(check("application/xhtml+xml", b"<!DOCTYPE html>"), None);
+        assert_eq!(check("application/xml", b"<svg>"), None);
+        assert_eq!(check("text/xml", b"<?xml version=\"1.0\"?><svg>"), None);
+        assert_eq!(
+            check("application/xhtml+xml", b"<svg>"),
+            Some("image/svg+xml")
+        );
+        assert_eq!(check("application/octet-stream", b"<html>"), None);
+        assert_eq!(check("image/png", b"\x89PNG\r\n\x1a\n"), None);
+        // images are only compared with other image types
+        assert_eq!(check("application/zip", b"\x89PNG\r\n\x1a\n"), None);
+        assert_eq!(check("text/csv", b"<htmlish,csv"), None);
+        assert_eq!(check("text/csv", b"id,name\n"), None);
+    }
+
+    #[tokio::test]
+    async fn blocks_spoofed_content() {
+        let response = Attachment::new("<html><script>alert(1)</script>")
+            .content_type("image/png")
+            .sniff_content(ContentSniffing::Block)
+            .into_response();
+
+        let err = response.into_body().collect().await.unwrap_err();
+        let err = err.into_inner().downcast::<SpoofedContent>().unwrap();
+        assert_eq!(err.declared(), "image/png");
+        assert_eq!(err.detected(), "text/html");
+    }
+
+    #[tokio::test]
+    async fn sends_matching_content() {
+        let response = Attachment::new("<html></html>")
+            .content_type("text/html")
+            .sniff_content(ContentSniffing::Block)
+            .into_response();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "<html></html>");
+    }
+}
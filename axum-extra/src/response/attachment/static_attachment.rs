@@ -0,0 +1,154 @@
+use super::Attachment;
+use axum::{
+    body::Bytes,
+    response::{IntoResponse, Response},
+};
+use http::HeaderValue;
+
+/// An [`Attachment`] of static data that can be created in `const` and `static` items.
+///
+/// This is useful for serving assets embedded with [`include_bytes!`] without any setup. The body
+/// is never copied.
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{routing::get, Router};
+/// use axum_extra::response::attachment::StaticAttachment;
+///
+/// static TEMPLATE: StaticAttachment = StaticAttachment::new(b"id,name,email\n")
+///     .filename("import-template.csv")
+///     .content_type("text/csv");
+///
+/// let app = Router::new().route("/import-template.csv", get(|| async { TEMPLATE }));
+/// # let _: Router = app;
+/// ```
+///
+/// Use [`Attachment::from`] to change other settings:
+///
+/// ```rust
+/// # use axum_extra::response::attachment::StaticAttachment;
+/// use axum::body::Bytes;
+/// use axum_extra::response::Attachment;
+///
+/// # static TEMPLATE: StaticAttachment = StaticAttachment::new(b"id,name,email\n");
+/// async fn template() -> Attachment<Bytes> {
+///     Attachment::from(TEMPLATE).nosniff()
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub struct StaticAttachment {
+    body: &'static [u8],
+    filename: Option<&'static str>,
+    content_type: Option<&'static str>,
+}
+
+impl StaticAttachment {
+    /// Create a new `StaticAttachment` sending `body`.
+    pub const fn new(body: &'static [u8]) -> Self {
+        Self {
+            body,
+            filename: None,
+            content_type: None,
+        }
+    }
+
+    /// Sets the filename of the attachment.
+    ///
+    /// The filename is processed like one passed to [`Attachment::filename`].
+    pub const fn filename(self, filename: &'static str) -> Self {
+        Self {
+            filename: Some(filename),
+            ..self
+        }
+    }
+
+    /// Sets the content-type of the attachment.
+    ///
+    /// # Panics
+    ///
+    /// If `content_type` isn't a valid header value. When used in a `const` or `static` item, this
+    /// is a compile error.
+    pub const fn content_type(self, content_type: &'static str) -> Self {
+        let bytes = content_type.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            let b = bytes[i];
+            assert!((b >= 32 && b < 127) || b == b'\t', "invalid content type");
+            i += 1;
+        }
+
+        Self {
+            content_type: Some(content_type),
+            ..self
+        }
+    }
+}
+
+impl From<StaticAttachment> for Attachment<Bytes> {
+    fn from(attachment: StaticAttachment) -> Self {
+        let mut this = Attachment::new(Bytes::from_static(attachment.body));
+        if let Some(filename) = attachment.filename {
+            this = this.filename(filename);
+        }
+        if let Some(content_type) = attachment.content_type {
+            this = this.content_type(HeaderValue::from_static(content_type));
+        }
+        this
+    }
+}
+
+impl IntoResponse for StaticAttachment {
+    fn into_response(self) -> Response {
+        Attachment::from(self).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::response::AttachmentInfo;
+    use http::header;
+
+    const LOGO: StaticAttachment = StaticAttachment::new(b"\x89PNG\r\n\x1a\n")
+        .filename("logo.png")
+        .content_type("image/png");
+
+    #[test]
+    fn static_attachment() {
+        let response = LOGO.into_response();
+
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"logo.png\""
+        );
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        let info = response.extensions().get::<AttachmentInfo>().unwrap();
+        assert_eq!(info.size(), Some(8));
+    }
+
+    #[test]
+    fn non_ascii_filename() {
+        static REPORT: StaticAttachment = StaticAttachment::new(b"").filename("résumé.pdf");
+
+        let response = REPORT.into_response();
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"r_sum_.pdf\"; filename*=UTF-8''r%C3%A9sum%C3%A9.pdf"
+        );
+    }
+
+    #[test]
+    #[should_panic = "invalid content type"]
+    fn invalid_content_type() {
+        let _ = StaticAttachment::new(b"").content_type("text/plain\n");
+    }
+
+    #[test]
+    #[should_panic = "invalid content type"]
+    fn non_ascii_content_type() {
+        // `HeaderValue::from_static` would panic on every request otherwise
+        let _ = StaticAttachment::new(b"").content_type("text/plain; name=é");
+    }
+}
@@ -0,0 +1,376 @@
+use axum::{
+    body::{Body, Bytes, HttpBody},
+    BoxError, Error,
+};
+use http_body::{Frame, SizeHint};
+use pin_project_lite::pin_project;
+use std::{
+    fmt,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tracing::{field::Empty, Span};
+
+/// Hook called when an [`Attachment`] fails while it is being sent.
+///
+/// This is only called when the body itself returns an error, such as a failing read from disk
+/// or an exceeded [deadline](super::Attachment::deadline). Clients that cancel the download
+/// don't cause an error, so they aren't reported. This makes it suitable for sending errors to
+/// an error tracker such as Sentry.
+///
+/// It is implemented for closures taking a [`FailedDownload`].
+///
+/// # Example
+///
+/// ```rust
+/// use axum::{body::Body, routing::get, Router};
+/// use axum_extra::response::{attachment::FailedDownload, Attachment};
+///
+/// async fn export() -> Attachment<Body> {
+///     # let body = Body::empty();
+///     Attachment::new(body)
+///         .filename("export.csv")
+///         .error_reporter(|failure: &FailedDownload<'_>| {
+///             eprintln!(
+///                 "sending {:?} failed after {} bytes: {}",
+///                 failure.filename(),
+///                 failure.bytes_sent(),
+///                 failure.error(),
+///             );
+///         })
+/// }
+///
+/// let app = Router::new().route("/export", get(export));
+/// # let _: Router = app;
+/// ```
+///
+/// [`Attachment`]: super::Attachment
+pub trait ErrorReporter: Send + Sync + 'static {
+    /// Report the failed download.
+    fn report(&self, failure: &FailedDownload<'_>);
+}
+
+impl<F> ErrorReporter for F
+where
+    F: Fn(&FailedDownload<'_>) + Send + Sync + 'static,
+{
+    fn report(&self, failure: &FailedDownload<'_>) {
+        self(failure)
+    }
+}
+
+#[derive(Clone)]
+pub(super) struct SharedErrorReporter(pub(super) Arc<dyn ErrorReporter>);
+
+impl fmt::Debug for SharedErrorReporter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ErrorReporter").finish()
+    }
+}
+
+/// A download that failed, passed to an [`ErrorReporter`].
+#[derive(Debug)]
+pub struct FailedDownload<'a> {
+    filename: Option<&'a str>,
+    bytes_sent: u64,
+    bytes_total: Option<u64>,
+    error: &'a (dyn std::error::Error + Send + Sync),
+}
+
+impl FailedDownload<'_> {
+    /// The filename sent to the client, if any.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename
+    }
+
+    /// The number of bytes sent before the download failed.
+    pub fn bytes_sent(&self) -> u64 {
+        self.bytes_sent
+    }
+
+    /// The size of the body, if it was known up front.
+    pub fn bytes_total(&self) -> Option<u64> {
+        self.bytes_total
+    }
+
+    /// The error returned by the body.
+    pub fn error(&self) -> &(dyn std::error::Error + Send + Sync) {
+        self.error
+    }
+}
+
+pin_project! {
+    /// Body that reports its progress in a `download` span, and its errors to an
+    /// [`ErrorReporter`].
+    pub(super) struct TracedBody {
+        #[pin]
+        inner: Body,
+        span: Span,
+        reporter: Option<(SharedErrorReporter, Option<String>)>,
+        total: Option<u64>,
+        sent: u64,
+        done: bool,
+    }
+
+    impl PinnedDrop for TracedBody {
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+            if !*this.done {
+                this.span.record("download.bytes_sent", *this.sent);
+                this.span.record("download.status", "aborted");
+            }
+        }
+    }
+}
+
+impl TracedBody {
+    pub(super) fn new(
+        inner: Body,
+        filename: Option<&str>,
+        span: bool,
+        reporter: Option<SharedErrorReporter>,
+    ) -> Self {
+        let total = inner.size_hint().exact();
+        let span = if span {
+            tracing::info_span!(
+                "download",
+                download.filename = filename,
+                download.bytes_total = total,
+                download.bytes_sent = Empty,
+                download.status = Empty,
+            )
+        } else {
+            Span::none()
+        };
+
+        Self {
+            inner,
+            span,
+            reporter: reporter.map(|reporter| (reporter, filename.map(ToOwned::to_owned))),
+            total,
+            sent: 0,
+            done: false,
+        }
+    }
+}
+
+impl HttpBody for TracedBody {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let mut this = self.project();
+        let _guard = this.span.enter();
+
+        if *this.done {
+            return Poll::Ready(None);
+        }
+
+        let status = match this.inner.as_mut().poll_frame(cx) {
+            Poll::Pending => return Poll::Pending,
+            Poll::Ready(Some(Ok(frame))) => {
+                if let Some(data) = frame.data_ref() {
+                    *this.sent += data.len() as u64;
+                }
+                // hyper drops bodies without polling them to the end once they report the end of
+                // the stream, or once `Content-Length` bytes have been sent
+                if this.inner.is_end_stream() || Some(*this.sent) == *this.total {
+                    *this.done = true;
+                    this.span.record("download.bytes_sent", *this.sent);
+                    this.span.record("download.status", "completed");
+                }
+                return Poll::Ready(Some(Ok(frame)));
+            }
+            Poll::Ready(Some(Err(err))) => Err(err),
+            Poll::Ready(None) => Ok(()),
+        };
+
+        *this.done = true;
+        this.span.record("download.bytes_sent", *this.sent);
+        match status {
+            Ok(()) => {
+                this.span.record("download.status", "completed");
+                Poll::Ready(None)
+            }
+            Err(err) => {
+                this.span.record("download.status", "failed");
+                let err = Error::into_inner(err);
+                if let Some((reporter, filename)) = this.reporter {
+                    reporter.0.report(&FailedDownload {
+                        filename: filename.as_deref(),
+                        bytes_sent: *this.sent,
+                        bytes_total: *this.total,
+                        error: &*err,
+                    });
+                }
+                Poll::Ready(Some(Err(err)))
+            }
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done || self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FailedDownload;
+    use crate::{body::SizedStream, response::Attachment, test_helpers::*};
+    use axum::{body::Bytes, response::IntoResponse, routing::get, Router};
+    use futures_util::stream;
+    use http_body::Body as _;
+    use http_body_util::BodyExt;
+    use std::{
+        fmt,
+        sync::{Arc, Mutex},
+    };
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    /// Subscriber that records the `download.status` of every span.
+    #[derive(Clone, Default)]
+    struct Statuses(Arc<Mutex<Vec<String>>>);
+
+    impl Subscriber for Statuses {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(1)
+        }
+
+        fn record(&self, _: &span::Id, values: &span::Record<'_>) {
+            values.record(&mut StatusVisitor(&self.0));
+        }
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, _: &Event<'_>) {}
+
+        fn enter(&self, _: &span::Id) {}
+
+        fn exit(&self, _: &span::Id) {}
+    }
+
+    struct StatusVisitor<'a>(&'a Mutex<Vec<String>>);
+
+    impl Visit for StatusVisitor<'_> {
+        fn record_str(&mut self, field: &Field, value: &str) {
+            if field.name() == "download.status" {
+                self.0.lock().unwrap().push(value.to_owned());
+            }
+        }
+
+        fn record_debug(&mut self, _: &Field, _: &dyn fmt::Debug) {}
+    }
+
+    #[crate::test]
+    async fn sized_downloads_complete_over_http() {
+        let statuses = Statuses::default();
+        let _guard = tracing::subscriber::set_default(statuses.clone());
+
+        let app = Router::new()
+            .route(
+                "/bytes",
+                get(|| async { Attachment::new("hello").download_span() }),
+            )
+            .route(
+                "/stream",
+                get(|| async {
+                    let chunks = stream::iter(["hel", "lo"].map(Ok::<_, std::io::Error>));
+                    Attachment::new(SizedStream::new(chunks, 5)).download_span()
+                }),
+            );
+        let client = TestClient::new(app);
+
+        for path in ["/bytes", "/stream"] {
+            let res = client.get(path).await;
+            assert_eq!(res.text().await, "hello");
+        }
+        tokio::task::yield_now().await;
+
+        assert_eq!(*statuses.0.lock().unwrap(), ["completed", "completed"]);
+    }
+
+    #[tokio::test]
+    async fn passes_body_through() {
+        let response = Attachment::new("hello")
+            .filename("hello.txt")
+            .download_span()
+            .into_response();
+
+        assert_eq!(response.body().size_hint().exact(), Some(5));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "hello");
+    }
+
+    #[tokio::test]
+    async fn passes_errors_through() {
+        let body = axum::body::Body::from_stream(stream::iter([
+            Ok(Bytes::from("hello")),
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "boom")),
+        ]));
+        let response = Attachment::new(body).download_span().into_response();
+
+        let err = response.into_body().collect().await.unwrap_err();
+        assert_eq!(err.to_string(), "boom");
+    }
+
+    #[tokio::test]
+    async fn reports_errors() {
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let reporter = {
+            let reports = reports.clone();
+            move |failure: &FailedDownload<'_>| {
+                reports.lock().unwrap().push((
+                    failure.filename().map(ToOwned::to_owned),
+                    failure.bytes_sent(),
+                    failure.bytes_total(),
+                    failure.error().to_string(),
+                ));
+            }
+        };
+
+        let body = axum::body::Body::from_stream(stream::iter([
+            Ok(Bytes::from("hello")),
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "boom")),
+        ]));
+        let response = Attachment::new(body)
+            .filename("export.csv")
+            .error_reporter(reporter.clone())
+            .into_response();
+        response.into_body().collect().await.unwrap_err();
+
+        // cancelled downloads aren't reported
+        let body =
+            axum::body::Body::from_stream(stream::pending::<Result<Bytes, std::io::Error>>());
+        drop(
+            Attachment::new(body)
+                .error_reporter(reporter.clone())
+                .into_response(),
+        );
+
+        // neither are completed ones
+        let response = Attachment::new("hello")
+            .error_reporter(reporter)
+            .into_response();
+        response.into_body().collect().await.unwrap();
+
+        assert_eq!(
+            *reports.lock().unwrap(),
+            [(Some("export.csv".to_owned()), 5, None, "boom".to_owned())]
+        );
+    }
+}
@@ -4,7 +4,7 @@
 mod erased_json;
 
 #[cfg(feature = "attachment")]
-mod attachment;
+pub mod attachment;
 
 #[cfg(feature = "erased-json")]
 pub use erased_json::ErasedJson;
@@ -14,7 +14,7 @@ pub use erased_json::ErasedJson;
 pub use crate::json_lines::JsonLines;
 
 #[cfg(feature = "attachment")]
-pub use attachment::Attachment;
+pub use attachment::{Attachment, AttachmentInfo};
 
 macro_rules! mime_response {
     (